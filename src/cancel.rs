@@ -0,0 +1,61 @@
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Panic payload used to unwind out of a cancelled solution.
+#[derive(Debug)]
+pub struct Cancelled;
+
+/// Shared flag that asks a run to stop at its next [`checkpoint`].
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// Makes `token` the one checked by [`checkpoint`] on this thread. The runner
+/// installs it on the solving thread and on every worker of its rayon pool.
+pub fn set_current(token: Option<CancelToken>) {
+    CURRENT.with(|current| *current.borrow_mut() = token);
+}
+
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    })
+}
+
+/// Unwinds with a [`Cancelled`] payload if the current run has been cancelled.
+///
+/// Call this in loops and recursion that may run for a long time. The unwind
+/// skips the panic hook, passes through rayon workers and is caught by the
+/// runner, so solutions keep returning a plain `String`. Outside of a runner
+/// this never unwinds.
+#[inline]
+pub fn checkpoint() {
+    if is_cancelled() {
+        std::panic::resume_unwind(Box::new(Cancelled));
+    }
+}
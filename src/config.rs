@@ -0,0 +1,212 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::solution::Solution;
+
+pub const FILE_NAME: &str = "aoc.toml";
+
+#[derive(Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Runner settings shared by every subcommand, read from `aoc.toml`.
+///
+/// ```toml
+/// input_dir = "input"
+/// session_file = "~/.config/aoc2024/session"
+/// format = "json"
+/// timeout = 30.0
+/// threads = 4
+/// skip = [6]
+/// ```
+#[derive(Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Directory with real inputs named by day number, read at run time
+    /// instead of the inputs built into the binary.
+    pub input_dir: Option<PathBuf>,
+    /// File holding the adventofcode.com session token. Read by nothing yet:
+    /// the `fetch` and `submit` subcommands that need it are still to come.
+    pub session_file: Option<PathBuf>,
+    pub format: Option<Format>,
+    /// Per-part timeout in seconds.
+    pub timeout: Option<f64>,
+    /// Size of the rayon pool each part runs on.
+    pub threads: Option<usize>,
+    /// Days left out unless they are asked for by number.
+    pub skip: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Parse(path, error) => write!(f, "{}: {}", path.display(), error),
+            ConfigError::Invalid(path, error) => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn user_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir));
+    }
+
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+}
+
+/// Timeout of `seconds`, or `None` unless it is positive and fits in a
+/// [`Duration`].
+pub fn timeout_from_secs(seconds: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|timeout| !timeout.is_zero())
+}
+
+/// Expands a leading `~/` to the home directory.
+fn expand_home(path: PathBuf) -> PathBuf {
+    let Ok(rest) = path.strip_prefix("~") else {
+        return path;
+    };
+
+    match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(rest),
+        None => path,
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let mut config: Config =
+            toml::from_str(text).map_err(|error| ConfigError::Parse(path.to_owned(), error))?;
+
+        config.input_dir = config.input_dir.map(expand_home);
+        config.session_file = config.session_file.map(expand_home);
+
+        if let Some(seconds) = config.timeout {
+            if timeout_from_secs(seconds).is_none() {
+                return Err(ConfigError::Invalid(
+                    path.to_owned(),
+                    format!(
+                        "timeout must be a positive number of seconds, got {:?}",
+                        seconds
+                    ),
+                ));
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let text =
+            fs::read_to_string(path).map_err(|error| ConfigError::Io(path.to_owned(), error))?;
+
+        Self::parse(&text, path)
+    }
+
+    /// Candidate locations: the project root, then the user's config directory.
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(FILE_NAME)];
+
+        if let Some(dir) = user_config_dir() {
+            paths.push(dir.join("aoc2024").join(FILE_NAME));
+        }
+
+        paths
+    }
+
+    /// Loads the first config file found, or the defaults if there is none.
+    pub fn load() -> Result<Self, ConfigError> {
+        match Self::search_paths().into_iter().find(|path| path.is_file()) {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.and_then(timeout_from_secs)
+    }
+
+    /// Real input for `solution`, from `input_dir` when it is set.
+    pub fn input(&self, solution: &Solution) -> io::Result<String> {
+        match self.input_dir.as_ref() {
+            Some(dir) => fs::read_to_string(dir.join(solution.day.to_string())),
+            None => Ok(solution.input.to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, time::Duration};
+
+    use crate::config::{Config, Format};
+
+    #[test]
+    fn parse_test() {
+        let config = Config::parse(
+            "input_dir = \"data\"\nformat = \"json\"\ntimeout = 1.5\nthreads = 2\nskip = [6, 9]\n",
+            Path::new("aoc.toml"),
+        )
+        .unwrap();
+
+        assert_eq!(config.input_dir, Some("data".into()));
+        assert_eq!(config.format, Some(Format::Json));
+        assert_eq!(config.timeout(), Some(Duration::from_millis(1500)));
+        assert_eq!(config.threads, Some(2));
+        assert_eq!(config.skip, vec![6, 9]);
+        assert_eq!(config.session_file, None);
+
+        let config = Config::parse("session_file = \"token\"", Path::new("aoc.toml")).unwrap();
+        assert_eq!(config.session_file, Some("token".into()));
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert!(Config::parse("format = \"yaml\"", Path::new("aoc.toml")).is_err());
+        assert!(Config::parse("unknown = 1", Path::new("aoc.toml")).is_err());
+
+        for timeout in ["-1.0", "0.0", "nan", "inf", "1e300"] {
+            let text = format!("timeout = {}", timeout);
+            assert!(
+                Config::parse(&text, Path::new("aoc.toml")).is_err(),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            Config::parse("", Path::new("aoc.toml")).unwrap(),
+            Config::default()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::{Add, BitAnd, BitOr, Sub},
+};
+
+/// Multiset that counts how often each value occurs.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Counter<T: Hash + Eq> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Hash + Eq> Default for Counter<T> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> Counter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, value: T) {
+        self.add_n(value, 1);
+    }
+
+    pub fn add_n(&mut self, value: T, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        *self.counts.entry(value).or_default() += count;
+    }
+
+    /// Removes up to `count` occurrences of `value`.
+    pub fn remove_n(&mut self, value: &T, count: usize) {
+        let Some(existing) = self.counts.get_mut(value) else {
+            return;
+        };
+
+        if *existing <= count {
+            self.counts.remove(value);
+        } else {
+            *existing -= count;
+        }
+    }
+
+    pub fn get(&self, value: &T) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    /// Number of distinct values.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Sum of all counts.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(value, count)| (value, *count))
+    }
+}
+
+impl<T: Hash + Eq + Ord> Counter<T> {
+    /// Entries ordered by value.
+    pub fn sorted(&self) -> Vec<(&T, usize)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by_key(|(value, _)| *value);
+        entries
+    }
+
+    /// The `k` most frequent entries, most frequent first. Ties are ordered by
+    /// value so the result does not depend on hash order.
+    pub fn most_common(&self, k: usize) -> Vec<(&T, usize)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| a.cmp(b))
+        });
+        entries.truncate(k);
+        entries
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for Counter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+/// Sum of both counts.
+impl<T: Hash + Eq + Clone> Add for &Counter<T> {
+    type Output = Counter<T>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut counter = self.clone();
+
+        for (value, count) in rhs.iter() {
+            counter.add_n(value.clone(), count);
+        }
+
+        counter
+    }
+}
+
+/// Counts of `self` minus counts of `rhs`, dropping values that reach zero.
+impl<T: Hash + Eq + Clone> Sub for &Counter<T> {
+    type Output = Counter<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut counter = self.clone();
+
+        for (value, count) in rhs.iter() {
+            counter.remove_n(value, count);
+        }
+
+        counter
+    }
+}
+
+/// Intersection, keeping the smaller count of values present in both.
+impl<T: Hash + Eq + Clone> BitAnd for &Counter<T> {
+    type Output = Counter<T>;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        let mut counter = Counter::new();
+
+        for (value, count) in self.iter() {
+            counter.add_n(value.clone(), count.min(rhs.get(value)));
+        }
+
+        counter
+    }
+}
+
+/// Union, keeping the larger count of values present in either.
+impl<T: Hash + Eq + Clone> BitOr for &Counter<T> {
+    type Output = Counter<T>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        let mut counter = self.clone();
+
+        for (value, count) in rhs.iter() {
+            let existing = counter.get(value);
+
+            if count > existing {
+                counter.add_n(value.clone(), count - existing);
+            }
+        }
+
+        counter
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::counter::Counter;
+
+    #[test]
+    fn most_common_test() {
+        let counter: Counter<char> = "mississippi".chars().collect();
+
+        assert_eq!(counter.get(&'s'), 4);
+        assert_eq!(counter.get(&'x'), 0);
+        assert_eq!(counter.len(), 4);
+        assert_eq!(counter.total(), 11);
+        assert_eq!(counter.most_common(2), vec![(&'i', 4), (&'s', 4)]);
+        assert_eq!(
+            counter.sorted(),
+            vec![(&'i', 4), (&'m', 1), (&'p', 2), (&'s', 4)]
+        );
+    }
+
+    #[test]
+    fn arithmetic_test() {
+        let a: Counter<u32> = [1, 1, 2, 3].into_iter().collect();
+        let b: Counter<u32> = [1, 2, 2, 4].into_iter().collect();
+
+        assert_eq!((&a + &b).sorted(), vec![(&1, 3), (&2, 3), (&3, 1), (&4, 1)]);
+        assert_eq!((&a - &b).sorted(), vec![(&1, 1), (&3, 1)]);
+        assert_eq!((&a & &b).sorted(), vec![(&1, 1), (&2, 1)]);
+        assert_eq!((&a | &b).sorted(), vec![(&1, 2), (&2, 2), (&3, 1), (&4, 1)]);
+    }
+}
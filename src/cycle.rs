@@ -0,0 +1,209 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
+/// Shape of an eventually periodic sequence `x0, f(x0), f(f(x0)), ...`.
+///
+/// The first `tail` states are visited once, after which the sequence repeats
+/// every `length` steps.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Cycle {
+    pub tail: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Smallest step count that reaches the same state as `steps` steps.
+    pub fn equivalent_steps(&self, steps: usize) -> usize {
+        if steps < self.tail {
+            return steps;
+        }
+
+        self.tail + (steps - self.tail) % self.length
+    }
+}
+
+/// Records states in the order they are visited and reports a cycle as soon as
+/// one repeats. Lookups are O(1) as opposed to scanning a `Vec`.
+#[derive(Clone, Debug)]
+pub struct History<S> {
+    seen: HashMap<S, usize>,
+}
+
+impl<S: Hash + Eq> Default for History<S> {
+    fn default() -> Self {
+        History {
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Hash + Eq> History<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `state` as the next step. Returns the cycle if `state` has been
+    /// recorded before, in which case the history is left unchanged.
+    pub fn record(&mut self, state: S) -> Option<Cycle> {
+        let steps = self.seen.len();
+
+        match self.seen.entry(state) {
+            Entry::Occupied(entry) => Some(Cycle {
+                tail: *entry.get(),
+                length: steps - entry.get(),
+            }),
+            Entry::Vacant(entry) => {
+                entry.insert(steps);
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, state: &S) -> bool {
+        self.seen.contains_key(state)
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Recorded states, in no particular order.
+    pub fn states(&self) -> impl Iterator<Item = &S> {
+        self.seen.keys()
+    }
+}
+
+/// Finds the cycle using a map of every visited state. Uses more memory than
+/// [`brent`] but calls `step` exactly `tail + length` times.
+pub fn find<S, F>(initial: S, mut step: F) -> Cycle
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut history = History::new();
+    let mut state = initial;
+
+    loop {
+        if let Some(cycle) = history.record(state.clone()) {
+            return cycle;
+        }
+
+        state = step(&state);
+    }
+}
+
+/// Finds the cycle with Brent's algorithm, keeping only two states in memory.
+pub fn brent<S, F>(initial: S, mut step: F) -> Cycle
+where
+    S: PartialEq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+
+    let mut tail = 0;
+
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        tail += 1;
+    }
+
+    Cycle { tail, length }
+}
+
+/// State after `steps` applications of `step`, skipping whole cycles so that
+/// huge step counts finish in `O(tail + length)` steps.
+pub fn state_after<S, F>(initial: S, mut step: F, steps: usize) -> S
+where
+    S: PartialEq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let cycle = brent(initial.clone(), &mut step);
+    let mut state = initial;
+
+    for _ in 0..cycle.equivalent_steps(steps) {
+        state = step(&state);
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cycle::{brent, find, state_after, Cycle, History};
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 2 -> ...
+    fn step(value: &u32) -> u32 {
+        if *value == 5 {
+            2
+        } else {
+            value + 1
+        }
+    }
+
+    #[test]
+    fn find_test() {
+        assert_eq!(find(0, step), Cycle { tail: 2, length: 4 });
+
+        let mut calls = 0;
+        let cycle = find(0, |value| {
+            calls += 1;
+            step(value)
+        });
+        assert_eq!(calls, cycle.tail + cycle.length);
+    }
+
+    #[test]
+    fn brent_test() {
+        assert_eq!(brent(0, step), Cycle { tail: 2, length: 4 });
+        assert_eq!(brent(3, step), Cycle { tail: 0, length: 4 });
+        assert_eq!(brent(7, |_| 7), Cycle { tail: 0, length: 1 });
+    }
+
+    #[test]
+    fn state_after_test() {
+        assert_eq!(state_after(0, step, 0), 0);
+        assert_eq!(state_after(0, step, 1), 1);
+        assert_eq!(state_after(0, step, 6), 2);
+        assert_eq!(state_after(0, step, 1_000_000_000_000), 4);
+        assert_eq!(state_after(0, step, 1_000_000_000_001), 5);
+    }
+
+    #[test]
+    fn history_test() {
+        let mut history = History::new();
+
+        assert_eq!(history.record('a'), None);
+        assert_eq!(history.record('b'), None);
+        assert_eq!(history.record('c'), None);
+        assert_eq!(history.record('b'), Some(Cycle { tail: 1, length: 2 }));
+        assert_eq!(history.len(), 3);
+    }
+}
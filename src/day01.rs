@@ -1,6 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    fmt::Display,
+    io::{self, BufRead, BufReader, Read},
+    iter::Peekable,
+};
 
-pub fn part_1(input: &str) -> String {
+use serde::Serialize;
+
+use crate::{
+    counter::Counter,
+    external_sort::{ExternalSort, Merge},
+};
+
+/// Values per column kept in memory by the streaming solvers before a sorted
+/// run is written to disk.
+pub const CHUNK_SIZE: usize = 1 << 20;
+
+fn columns(input: &str) -> (Vec<u32>, Vec<u32>) {
     let mut left: Vec<u32> = vec![];
     let mut right: Vec<u32> = vec![];
 
@@ -14,12 +30,18 @@ pub fn part_1(input: &str) -> String {
         right.push(right_str.parse().unwrap());
     }
 
+    (left, right)
+}
+
+pub fn part_1(input: &str) -> String {
+    let (mut left, mut right) = columns(input);
+
     left.sort();
     right.sort();
 
     let mut sum = 0;
 
-    for (left, right) in left.into_iter().zip(right.into_iter()) {
+    for (left, right) in left.into_iter().zip(right) {
         sum += left.abs_diff(right);
     }
 
@@ -27,7 +49,7 @@ pub fn part_1(input: &str) -> String {
 }
 
 pub fn part_2(input: &str) -> String {
-    let mut right_freq: HashMap<u32, u32> = HashMap::new();
+    let mut right_freq: Counter<u32> = Counter::new();
     let mut left: Vec<u32> = vec![];
 
     for line in input.lines() {
@@ -37,28 +59,268 @@ pub fn part_2(input: &str) -> String {
         let right_value: u32 = split.next().unwrap().parse().unwrap();
 
         left.push(left_value);
-
-        if let Some(entry) = right_freq.get_mut(&right_value) {
-            *entry += 1;
-        } else {
-            right_freq.insert(right_value, 1);
-        }
+        right_freq.add(right_value);
     }
 
     let mut sum = 0;
 
     for entry in left {
-        let freq = *(right_freq.get(&entry).unwrap_or(&0));
-        sum += entry * freq;
+        sum += entry as usize * right_freq.get(&entry);
     }
 
     sum.to_string()
 }
 
+/// The n-th smallest ids of both lists.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pair {
+    pub left: u32,
+    pub right: u32,
+    pub distance: u32,
+}
+
+/// Share of the similarity score from one id of the left list.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Contribution {
+    pub value: u32,
+    pub left_count: usize,
+    pub right_count: usize,
+    pub score: u64,
+}
+
+/// Both answers broken down into the terms they sum.
+#[derive(Serialize, Debug)]
+pub struct PairReport {
+    pub distance: u64,
+    pub similarity: u64,
+    /// Pairs in sorted order.
+    pub pairs: Vec<Pair>,
+    /// One entry per distinct left id, by id.
+    pub contributions: Vec<Contribution>,
+    /// Pairs with the largest distances, largest first.
+    pub top_pairs: Vec<Pair>,
+    /// Ids with the largest scores, largest first.
+    pub top_contributions: Vec<Contribution>,
+}
+
+impl PairReport {
+    /// Builds the report, keeping the `top` largest terms of each part.
+    pub fn new(input: &str, top: usize) -> Self {
+        let (mut left, mut right) = columns(input);
+
+        left.sort();
+        right.sort();
+
+        let pairs: Vec<Pair> = left
+            .iter()
+            .zip(right.iter())
+            .map(|(&left, &right)| Pair {
+                left,
+                right,
+                distance: left.abs_diff(right),
+            })
+            .collect();
+
+        let left_freq: Counter<u32> = left.iter().copied().collect();
+        let right_freq: Counter<u32> = right.iter().copied().collect();
+
+        let contributions: Vec<Contribution> = left_freq
+            .sorted()
+            .into_iter()
+            .map(|(&value, left_count)| {
+                let right_count = right_freq.get(&value);
+
+                Contribution {
+                    value,
+                    left_count,
+                    right_count,
+                    score: value as u64 * left_count as u64 * right_count as u64,
+                }
+            })
+            .collect();
+
+        let mut top_pairs = pairs.clone();
+        top_pairs.sort_by_key(|pair| Reverse(pair.distance));
+        top_pairs.truncate(top);
+
+        let mut top_contributions = contributions.clone();
+        top_contributions.sort_by_key(|contribution| Reverse(contribution.score));
+        top_contributions.truncate(top);
+
+        PairReport {
+            distance: pairs.iter().map(|pair| pair.distance as u64).sum(),
+            similarity: contributions.iter().map(|c| c.score).sum(),
+            pairs,
+            contributions,
+            top_pairs,
+            top_contributions,
+        }
+    }
+}
+
+impl Display for Pair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>10} {:>10} {:>10}",
+            self.left, self.right, self.distance
+        )
+    }
+}
+
+impl Display for Contribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>10} {:>6} {:>6} {:>12}",
+            self.value, self.left_count, self.right_count, self.score
+        )
+    }
+}
+
+impl Display for PairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Day=1, Distance={}, Similarity={}",
+            self.distance, self.similarity
+        )?;
+
+        writeln!(f, "  {:>10} {:>10} {:>10}", "left", "right", "distance")?;
+        for pair in self.pairs.iter() {
+            writeln!(f, "  {}", pair)?;
+        }
+
+        writeln!(f, "Top distances:")?;
+        for pair in self.top_pairs.iter() {
+            writeln!(f, "  {}", pair)?;
+        }
+
+        writeln!(
+            f,
+            "  {:>10} {:>6} {:>6} {:>12}",
+            "value", "left", "right", "score"
+        )?;
+        for contribution in self.contributions.iter() {
+            writeln!(f, "  {}", contribution)?;
+        }
+
+        writeln!(f, "Top contributors:")?;
+        for contribution in self.top_contributions.iter() {
+            writeln!(f, "  {}", contribution)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("expected two location ids, got {:?}", line.trim_end()),
+    )
+}
+
+/// Both columns of `reader`, each sorted with at most `chunk_size` values in
+/// memory.
+fn sorted_columns<R: Read>(reader: R, chunk_size: usize) -> io::Result<(Merge, Merge)> {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    let mut left = ExternalSort::new(chunk_size);
+    let mut right = ExternalSort::new(chunk_size);
+
+    while reader.read_line(&mut line)? > 0 {
+        let mut split = line.split_whitespace();
+
+        if let Some(left_str) = split.next() {
+            let right_str = split.next().ok_or_else(|| invalid_line(&line))?;
+
+            left.push(left_str.parse().map_err(|_| invalid_line(&line))?)?;
+            right.push(right_str.parse().map_err(|_| invalid_line(&line))?)?;
+        }
+
+        line.clear();
+    }
+
+    Ok((left.finish()?, right.finish()?))
+}
+
+/// Part 1 over a list that need not fit in memory.
+pub fn distance<R: Read>(reader: R, chunk_size: usize) -> io::Result<u64> {
+    let (left, right) = sorted_columns(reader, chunk_size)?;
+    let mut sum = 0;
+
+    for (left, right) in left.zip(right) {
+        sum += left?.abs_diff(right?) as u64;
+    }
+
+    Ok(sum)
+}
+
+/// Next value of a sorted stream with the number of times it repeats.
+fn next_group(values: &mut Peekable<Merge>) -> io::Result<Option<(u32, u64)>> {
+    let Some(value) = values.next().transpose()? else {
+        return Ok(None);
+    };
+
+    let mut count = 1;
+
+    while let Some(Ok(next)) = values.peek() {
+        if *next != value {
+            break;
+        }
+
+        count += 1;
+        values.next();
+    }
+
+    Ok(Some((value, count)))
+}
+
+/// Part 2 over a list that need not fit in memory, counting equal values as
+/// the two sorted columns are walked side by side.
+pub fn similarity<R: Read>(reader: R, chunk_size: usize) -> io::Result<u64> {
+    let (left, right) = sorted_columns(reader, chunk_size)?;
+    let mut left = left.peekable();
+    let mut right = right.peekable();
+
+    let mut left_group = next_group(&mut left)?;
+    let mut right_group = next_group(&mut right)?;
+    let mut sum = 0;
+
+    while let (Some((left_value, left_count)), Some((right_value, right_count))) =
+        (left_group, right_group)
+    {
+        if left_value <= right_value {
+            left_group = next_group(&mut left)?;
+        }
+
+        if right_value <= left_value {
+            right_group = next_group(&mut right)?;
+        }
+
+        if left_value == right_value {
+            sum += left_value as u64 * left_count * right_count;
+        }
+    }
+
+    Ok(sum)
+}
+
+pub fn part_1_streaming(input: &str) -> String {
+    distance(input.as_bytes(), CHUNK_SIZE).unwrap().to_string()
+}
+
+pub fn part_2_streaming(input: &str) -> String {
+    similarity(input.as_bytes(), CHUNK_SIZE)
+        .unwrap()
+        .to_string()
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
-        day01::{part_1, part_2},
+        day01::{distance, part_1, part_2, similarity, Contribution, PairReport},
         input, output,
     };
 
@@ -80,4 +342,65 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_01), output::DAY_01_2)
     }
+
+    #[test]
+    fn streaming_matches_in_memory() {
+        for chunk_size in [1, 7, 100, 1 << 20] {
+            assert_eq!(
+                distance(input::DAY_01.as_bytes(), chunk_size)
+                    .unwrap()
+                    .to_string(),
+                output::DAY_01_1
+            );
+            assert_eq!(
+                similarity(input::DAY_01.as_bytes(), chunk_size)
+                    .unwrap()
+                    .to_string(),
+                output::DAY_01_2
+            );
+        }
+
+        assert_eq!(
+            similarity(input::example::DAY_01.as_bytes(), 2)
+                .unwrap()
+                .to_string(),
+            output::example::DAY_01_2
+        );
+        assert!(distance("3 4\n5\n".as_bytes(), 8).is_err());
+    }
+
+    #[test]
+    fn pair_report_example() {
+        let report = PairReport::new(input::example::DAY_01, 2);
+
+        assert_eq!(report.distance.to_string(), output::example::DAY_01_1);
+        assert_eq!(report.similarity.to_string(), output::example::DAY_01_2);
+        assert_eq!(report.pairs.len(), 6);
+        assert_eq!(
+            report
+                .top_pairs
+                .iter()
+                .map(|pair| pair.distance)
+                .collect::<Vec<_>>(),
+            vec![5, 2]
+        );
+        assert_eq!(
+            report.top_contributions[0],
+            Contribution {
+                value: 3,
+                left_count: 3,
+                right_count: 3,
+                score: 27
+            }
+        );
+    }
+
+    #[test]
+    fn pair_report_real() {
+        let report = PairReport::new(input::DAY_01, 10);
+
+        assert_eq!(report.distance.to_string(), output::DAY_01_1);
+        assert_eq!(report.similarity.to_string(), output::DAY_01_2);
+        assert_eq!(report.top_pairs.len(), 10);
+    }
 }
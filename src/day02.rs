@@ -1,118 +1,402 @@
-#[derive(PartialEq, Eq, Clone)]
-enum Change {
-    Increase,
-    Decrease,
-    Invalid,
-    None,
+use std::{fmt::Display, ops::RangeInclusive};
+
+use serde::Serialize;
+
+/// Which way the levels of a safe report may move.
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Directions {
+    Increasing,
+    Decreasing,
+    /// All increasing or all decreasing.
+    Either,
+    /// Any mix of increases and decreases.
+    Mixed,
 }
 
-impl Change {
-    fn from_left_right(left: u32, right: u32) -> Self {
-        if left == right {
-            return Change::Invalid;
-        }
-        if left > right {
-            return Change::Increase;
+impl Directions {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "increasing" => Some(Directions::Increasing),
+            "decreasing" => Some(Directions::Decreasing),
+            "either" => Some(Directions::Either),
+            "mixed" => Some(Directions::Mixed),
+            _ => None,
         }
-        Change::Decrease
     }
+}
 
-    fn safe(&mut self, left: u32, right: u32) -> bool {
-        if self == &Change::Invalid {
-            return false;
-        }
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    DirectionFlip,
+    StepTooLarge,
+    /// Only possible when the rules ask for steps larger than 1.
+    StepTooSmall,
+    ZeroStep,
+}
 
-        let next = Change::from_left_right(left, right);
+/// First pair of neighbouring levels that breaks the rules.
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Violation {
+    /// Index of the left level of the pair.
+    pub index: usize,
+    pub levels: [u32; 2],
+    pub kind: ViolationKind,
+}
 
-        if self == &Change::None {
-            *self = Change::from_left_right(left, right);
-        }
+/// Why a report is or is not safe.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct Diagnosis {
+    pub line: usize,
+    pub report: Vec<u32>,
+    pub safe: bool,
+    /// First violation before the Problem Dampener removes anything.
+    pub violation: Option<Violation>,
+    /// Levels the Problem Dampener removed to make the report safe.
+    pub removed: Vec<usize>,
+}
+
+/// What makes a report safe.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct SafetyRules {
+    /// Allowed size of a change between neighbouring levels. A range that
+    /// includes 0 allows equal neighbours.
+    pub steps: RangeInclusive<u32>,
+    pub directions: Directions,
+    /// Equal neighbours are allowed even when `steps` does not include 0.
+    pub allow_equal: bool,
+    /// Levels the Problem Dampener may remove.
+    pub removals: usize,
+}
 
-        if self != &Change::from_left_right(left, right) {
-            return false;
+impl SafetyRules {
+    pub const PART_1: SafetyRules = SafetyRules {
+        steps: 1..=3,
+        directions: Directions::Either,
+        allow_equal: false,
+        removals: 0,
+    };
+
+    pub const PART_2: SafetyRules = SafetyRules {
+        removals: 1,
+        ..SafetyRules::PART_1
+    };
+
+    pub fn preset(one: bool) -> Self {
+        if one {
+            SafetyRules::PART_1
+        } else {
+            SafetyRules::PART_2
         }
+    }
 
-        let delta = left.abs_diff(right);
+    /// Whether neighbouring levels may be equal, by `allow_equal` or a zero
+    /// step in `steps`.
+    pub fn equal_allowed(&self) -> bool {
+        self.allow_equal || self.steps.contains(&0)
+    }
 
-        if !(1..=3).contains(&delta) {
-            return false;
+    /// Whether `right` may follow `left` when moving `up` or down, or either
+    /// way when `up` is `None`.
+    fn step(&self, left: u32, right: u32, up: Option<bool>) -> bool {
+        if left == right {
+            return self.equal_allowed();
         }
 
-        *self = next;
+        up.is_none_or(|up| up == (right > left)) && self.steps.contains(&left.abs_diff(right))
+    }
+
+    /// Fewest levels to remove, in ascending order, for `report` to be safe,
+    /// or `None` if that takes more than `removals`.
+    pub fn removed(&self, report: &[u32]) -> Option<Vec<usize>> {
+        let k = self.removals;
+        let up = || fewest_removals(report, k, |left, right| self.step(left, right, Some(true)));
+        let down = || fewest_removals(report, k, |left, right| self.step(left, right, Some(false)));
+
+        match self.directions {
+            Directions::Increasing => up(),
+            Directions::Decreasing => down(),
+            Directions::Mixed => {
+                fewest_removals(report, k, |left, right| self.step(left, right, None))
+            }
+            Directions::Either => match (up(), down()) {
+                (Some(up), Some(down)) if down.len() < up.len() => Some(down),
+                (Some(up), _) => Some(up),
+                (None, down) => down,
+            },
+        }
+    }
 
-        true
+    pub fn safe(&self, report: &[u32]) -> bool {
+        self.removed(report).is_some()
     }
 
-    fn safe_report(mut self, report: &[u32], dampener_available: bool) -> bool {
-        for window in report.windows(2) {
-            if !self.safe(window[0], window[1]) {
-                if !dampener_available {
-                    return false;
-                } else {
-                    for variant_index in 0..report.len() {
-                        let mut variant = vec![];
-                        for (index, entry) in report.iter().enumerate() {
-                            if index == variant_index {
-                                continue;
-                            }
+    /// First pair of levels that breaks the rules, ignoring `removals`.
+    pub fn first_violation(&self, report: &[u32]) -> Option<Violation> {
+        let mut up = match self.directions {
+            Directions::Increasing => Some(true),
+            Directions::Decreasing => Some(false),
+            Directions::Either | Directions::Mixed => None,
+        };
 
-                            variant.push(*entry);
-                        }
+        for (index, window) in report.windows(2).enumerate() {
+            let (left, right) = (window[0], window[1]);
+            let step = left.abs_diff(right);
 
-                        if Change::None.safe_report(&variant, false) {
-                            return true;
-                        }
-                    }
+            let kind = if left == right {
+                (!self.equal_allowed()).then_some(ViolationKind::ZeroStep)
+            } else if up.is_some_and(|up| up != (right > left)) {
+                Some(ViolationKind::DirectionFlip)
+            } else if step > *self.steps.end() {
+                Some(ViolationKind::StepTooLarge)
+            } else if step < *self.steps.start() {
+                Some(ViolationKind::StepTooSmall)
+            } else {
+                None
+            };
 
-                    return false;
-                }
+            if let Some(kind) = kind {
+                return Some(Violation {
+                    index,
+                    levels: [left, right],
+                    kind,
+                });
+            }
+
+            if left != right && self.directions == Directions::Either {
+                up.get_or_insert(right > left);
             }
         }
 
-        true
+        None
+    }
+
+    pub fn diagnose(&self, line: usize, report: Vec<u32>) -> Diagnosis {
+        let violation = self.first_violation(&report);
+        let removed = match violation {
+            Some(_) => self.removed(&report),
+            None => Some(vec![]),
+        };
+
+        Diagnosis {
+            line,
+            safe: removed.is_some(),
+            violation,
+            removed: removed.unwrap_or_default(),
+            report,
+        }
     }
 }
 
-pub fn part_1(input: &str) -> String {
-    let mut safe = 0;
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let violation = self
+            .violation
+            .map(|violation| {
+                format!(
+                    "{:?} at {} ({} -> {})",
+                    violation.kind, violation.index, violation.levels[0], violation.levels[1]
+                )
+            })
+            .unwrap_or_else(|| "none".to_owned());
 
-    for line in input.lines() {
-        let change = Change::None;
-        let report: Vec<u32> = line
-            .split_whitespace()
-            .map(|value| value.parse().unwrap())
+        let removed: Vec<String> = self
+            .removed
+            .iter()
+            .map(|&index| format!("{} ({})", index, self.report[index]))
             .collect();
 
-        if change.safe_report(&report, false) {
-            safe += 1;
+        write!(
+            f,
+            "Line={}, Report={:?}, Safe={}, Violation={}, Removed=[{}]",
+            self.line,
+            self.report,
+            self.safe,
+            violation,
+            removed.join(", ")
+        )
+    }
+}
+
+/// Changes to the rules on top of the preset of one part.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct RuleOverrides {
+    /// Part whose preset is changed, part 1 unless set.
+    pub part: Option<u8>,
+    pub steps: Option<RangeInclusive<u32>>,
+    pub directions: Option<Directions>,
+    pub allow_equal: bool,
+    pub removals: Option<usize>,
+}
+
+impl RuleOverrides {
+    /// Parses `1..=3` as well as `1-3`, or a single step size such as `2`.
+    pub fn parse_steps(value: &str) -> Option<RangeInclusive<u32>> {
+        let (start, end) = value
+            .split_once("..=")
+            .or_else(|| value.split_once('-'))
+            .unwrap_or((value, value));
+
+        Some(start.parse().ok()?..=end.parse().ok()?)
+    }
+
+    pub fn rules(&self) -> SafetyRules {
+        let mut rules = SafetyRules::preset(self.part != Some(2));
+
+        if let Some(steps) = self.steps.clone() {
+            rules.steps = steps;
         }
+        if let Some(directions) = self.directions {
+            rules.directions = directions;
+        }
+        if let Some(removals) = self.removals {
+            rules.removals = removals;
+        }
+
+        rules.allow_equal |= self.allow_equal;
+        rules
     }
+}
 
-    safe.to_string()
+/// Safe reports of an input under a set of rules.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct SafetyCount {
+    pub rules: SafetyRules,
+    pub safe: usize,
+    pub total: usize,
 }
 
-pub fn part_2(input: &str) -> String {
-    let mut safe = 0;
+impl SafetyCount {
+    pub fn new(input: &str, rules: SafetyRules) -> Self {
+        SafetyCount {
+            safe: count_safe(input, &rules),
+            total: input.lines().count(),
+            rules,
+        }
+    }
+}
 
-    for line in input.lines() {
-        let change = Change::None;
-        let report: Vec<u32> = line
-            .split_whitespace()
-            .map(|value| value.parse().unwrap())
-            .collect();
+impl Display for SafetyCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rules = &self.rules;
+
+        write!(
+            f,
+            "Day=2, Steps={}..={}, Directions={:?}, AllowEqual={}, Removals={}, Safe={}, Total={}",
+            rules.steps.start(),
+            rules.steps.end(),
+            rules.directions,
+            rules.allow_equal,
+            rules.removals,
+            self.safe,
+            self.total
+        )
+    }
+}
+
+pub fn parse_report(line: &str) -> Vec<u32> {
+    line.split_whitespace()
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+/// Fewest removals, at most `k`, after which every pair of neighbouring kept
+/// levels satisfies `step`.
+///
+/// `removals[i]` is the fewest removals among the levels before `i` such that
+/// `i` is kept. A level can only follow one of the `k + 1` levels before it,
+/// so this is O(n * k).
+fn fewest_removals(
+    report: &[u32],
+    k: usize,
+    step: impl Fn(u32, u32) -> bool,
+) -> Option<Vec<usize>> {
+    let mut removals: Vec<usize> = Vec::with_capacity(report.len());
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(report.len());
+
+    for (index, &level) in report.iter().enumerate() {
+        let mut best = (index, None);
+
+        for before in index.saturating_sub(k + 1)..index {
+            let skipped = removals[before] + index - before - 1;
 
-        if change.safe_report(&report, true) {
-            safe += 1;
+            if skipped < best.0 && step(report[before], level) {
+                best = (skipped, Some(before));
+            }
         }
+
+        removals.push(best.0);
+        previous.push(best.1);
+    }
+
+    let last = (0..report.len())
+        .filter(|&last| report.len() - last <= k + 1)
+        .min_by_key(|&last| removals[last] + report.len() - 1 - last);
+
+    let Some(last) = last else {
+        // Only an empty report has no level to keep.
+        return Some(vec![]);
+    };
+
+    if removals[last] + report.len() - 1 - last > k {
+        return None;
     }
 
-    safe.to_string()
+    let mut kept = vec![false; report.len()];
+    let mut cursor = Some(last);
+
+    while let Some(index) = cursor {
+        kept[index] = true;
+        cursor = previous[index];
+    }
+
+    Some((0..report.len()).filter(|&index| !kept[index]).collect())
+}
+
+/// Levels to remove, at most `k` of them, that leave `report` safe under the
+/// part 1 rules. Returns the fewest such levels in ascending order, or `None`
+/// if more than `k` are needed.
+pub fn safe_with_removals(report: &[u32], k: usize) -> Option<Vec<usize>> {
+    SafetyRules {
+        removals: k,
+        ..SafetyRules::PART_1
+    }
+    .removed(report)
+}
+
+/// Diagnosis of every report in `input`, with lines numbered from 1.
+pub fn diagnose(input: &str, rules: &SafetyRules) -> Vec<Diagnosis> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| rules.diagnose(index + 1, parse_report(line)))
+        .collect()
+}
+
+/// Number of reports in `input` that are safe under `rules`.
+pub fn count_safe(input: &str, rules: &SafetyRules) -> usize {
+    input
+        .lines()
+        .filter(|line| rules.safe(&parse_report(line)))
+        .count()
+}
+
+pub fn part_1(input: &str) -> String {
+    count_safe(input, &SafetyRules::PART_1).to_string()
+}
+
+pub fn part_2(input: &str) -> String {
+    count_safe(input, &SafetyRules::PART_2).to_string()
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        day02::{part_1, part_2},
+        day02::{
+            count_safe, diagnose, parse_report, part_1, part_2, safe_with_removals, Directions,
+            RuleOverrides, SafetyCount, SafetyRules, Violation, ViolationKind,
+        },
         input, output,
     };
 
@@ -133,4 +417,155 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_02), output::DAY_02_2)
     }
+
+    #[test]
+    fn removals_test() {
+        assert_eq!(safe_with_removals(&[7, 6, 4, 2, 1], 0), Some(vec![]));
+        assert_eq!(safe_with_removals(&[1, 3, 2, 4, 5], 1), Some(vec![2]));
+        assert_eq!(safe_with_removals(&[8, 6, 4, 4, 1], 1), Some(vec![3]));
+        assert_eq!(safe_with_removals(&[1, 2, 7, 8, 9], 1), None);
+        assert_eq!(
+            safe_with_removals(&[9, 1, 2, 3, 50, 4, 60], 3),
+            Some(vec![0, 4, 6])
+        );
+        assert_eq!(safe_with_removals(&[9, 1, 2, 3, 50, 4, 60], 2), None);
+        assert_eq!(safe_with_removals(&[5], 0), Some(vec![]));
+        assert_eq!(safe_with_removals(&[], 0), Some(vec![]));
+    }
+
+    fn fewest_by_brute_force(report: &[u32], k: usize) -> Option<usize> {
+        let safe = |removed: &[usize]| {
+            let kept: Vec<u32> = (0..report.len())
+                .filter(|index| !removed.contains(index))
+                .map(|index| report[index])
+                .collect();
+
+            safe_with_removals(&kept, 0).is_some()
+        };
+
+        (0..=k).find(|&count| match count {
+            0 => safe(&[]),
+            1 => (0..report.len()).any(|a| safe(&[a])),
+            _ => (0..report.len()).any(|a| (a + 1..report.len()).any(|b| safe(&[a, b]))),
+        })
+    }
+
+    #[test]
+    fn removals_match_brute_force() {
+        for line in input::DAY_02.lines() {
+            let report = parse_report(line);
+
+            for k in 0..=2 {
+                assert_eq!(
+                    safe_with_removals(&report, k).map(|removed| removed.len()),
+                    fewest_by_brute_force(&report, k),
+                    "{:?} with k = {}",
+                    report,
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rules_test() {
+        let rules = SafetyRules {
+            directions: Directions::Mixed,
+            ..SafetyRules::PART_1
+        };
+        assert!(rules.safe(&[1, 3, 2, 4]));
+        assert!(!SafetyRules::PART_1.safe(&[1, 3, 2, 4]));
+
+        let rules = SafetyRules {
+            directions: Directions::Decreasing,
+            ..SafetyRules::PART_1
+        };
+        assert!(rules.safe(&[7, 6, 4, 2, 1]));
+        assert!(!rules.safe(&[1, 3, 6, 7, 9]));
+
+        let rules = SafetyRules {
+            allow_equal: true,
+            ..SafetyRules::PART_1
+        };
+        assert!(rules.safe(&[8, 6, 4, 4, 1]));
+
+        let rules = SafetyRules {
+            steps: 0..=3,
+            ..SafetyRules::PART_1
+        };
+        assert!(rules.equal_allowed());
+        assert!(rules.safe(&[8, 6, 4, 4, 1]));
+        assert_eq!(rules.first_violation(&[8, 6, 4, 4, 1]), None);
+
+        let rules = SafetyRules {
+            steps: 1..=5,
+            ..SafetyRules::PART_1
+        };
+        assert_eq!(count_safe(input::example::DAY_02, &rules), 4);
+    }
+
+    #[test]
+    fn overrides_test() {
+        assert_eq!(RuleOverrides::parse_steps("1..=5"), Some(1..=5));
+        assert_eq!(RuleOverrides::parse_steps("2-4"), Some(2..=4));
+        assert_eq!(RuleOverrides::parse_steps("3"), Some(3..=3));
+        assert_eq!(RuleOverrides::parse_steps("1..5"), None);
+
+        let overrides = RuleOverrides {
+            part: Some(2),
+            steps: Some(1..=5),
+            ..RuleOverrides::default()
+        };
+        let count = SafetyCount::new(input::example::DAY_02, overrides.rules());
+
+        assert_eq!(count.rules.removals, 1);
+        assert_eq!((count.safe, count.total), (6, 6));
+    }
+
+    #[test]
+    fn diagnose_example() {
+        let diagnoses = diagnose(input::example::DAY_02, &SafetyRules::PART_2);
+        let kinds: Vec<_> = diagnoses
+            .iter()
+            .map(|diagnosis| diagnosis.violation.map(|violation| violation.kind))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                None,
+                Some(ViolationKind::StepTooLarge),
+                Some(ViolationKind::StepTooLarge),
+                Some(ViolationKind::DirectionFlip),
+                Some(ViolationKind::ZeroStep),
+                None
+            ]
+        );
+        assert_eq!(
+            diagnoses[3].violation,
+            Some(Violation {
+                index: 1,
+                levels: [3, 2],
+                kind: ViolationKind::DirectionFlip
+            })
+        );
+        assert_eq!(diagnoses[3].removed, vec![2]);
+        assert_eq!(diagnoses[4].removed, vec![3]);
+        assert!(!diagnoses[1].safe);
+        assert!(diagnoses[1].removed.is_empty());
+    }
+
+    #[test]
+    fn violation_matches_safety() {
+        for line in input::DAY_02.lines() {
+            let report = parse_report(line);
+
+            assert_eq!(
+                SafetyRules::PART_1.first_violation(&report).is_none(),
+                SafetyRules::PART_1.safe(&report),
+                "{:?}",
+                report
+            );
+        }
+    }
 }
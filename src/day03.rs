@@ -1,86 +1,479 @@
-fn after_keyword_indices(raw_chars: &[u8], keyword: &[u8]) -> Vec<usize> {
-    let mut indices = vec![];
+use std::io::{self, Read};
 
-    for (index, window) in raw_chars.windows(keyword.len()).enumerate() {
-        if window.eq(keyword) {
-            indices.push(index + keyword.len());
+use crate::search::KeywordFinder;
+
+/// Most operands an instruction can take.
+pub const MAX_ARITY: usize = 4;
+
+/// Most digits an operand can have.
+pub const MAX_DIGITS: usize = 3;
+
+/// What an instruction does to the interpreter, given its operands.
+pub type Semantics = fn(&mut Interpreter, &[u32]);
+
+/// Instruction written as `name(a,b,...)` with exactly `arity` operands.
+#[derive(Clone, Copy, Debug)]
+pub struct Instruction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub semantics: Semantics,
+}
+
+impl Instruction {
+    pub const fn new(name: &'static str, arity: usize, semantics: Semantics) -> Self {
+        assert!(arity <= MAX_ARITY);
+
+        Instruction {
+            name,
+            arity,
+            semantics,
         }
     }
+}
+
+fn mul(interpreter: &mut Interpreter, operands: &[u32]) {
+    interpreter.accumulate(operands[0] as i64 * operands[1] as i64);
+}
 
-    indices
+fn add(interpreter: &mut Interpreter, operands: &[u32]) {
+    interpreter.accumulate(operands[0] as i64 + operands[1] as i64);
 }
 
-fn index_of_closing_paren(raw_chars: &[u8]) -> Option<usize> {
-    let needle = b')';
+fn sub(interpreter: &mut Interpreter, operands: &[u32]) {
+    interpreter.accumulate(operands[0] as i64 - operands[1] as i64);
+}
+
+fn enable(interpreter: &mut Interpreter, _operands: &[u32]) {
+    interpreter.enabled = true;
+}
+
+fn disable(interpreter: &mut Interpreter, _operands: &[u32]) {
+    interpreter.enabled = false;
+}
+
+fn toggle(interpreter: &mut Interpreter, _operands: &[u32]) {
+    interpreter.enabled = !interpreter.enabled;
+}
+
+fn toggle_if(interpreter: &mut Interpreter, operands: &[u32]) {
+    if operands[0] != 0 {
+        toggle(interpreter, operands);
+    }
+}
 
-    for (index, c) in raw_chars.iter().enumerate() {
-        if c == &needle {
-            return Some(index);
+pub const MUL: Instruction = Instruction::new("mul", 2, mul);
+pub const DO: Instruction = Instruction::new("do", 0, enable);
+pub const DONT: Instruction = Instruction::new("don't", 0, disable);
+pub const ADD: Instruction = Instruction::new("add", 2, add);
+pub const SUB: Instruction = Instruction::new("sub", 2, sub);
+pub const TOGGLE: Instruction = Instruction::new("toggle", 0, toggle);
+/// Toggles when its operand is not zero.
+pub const TOGGLE_IF: Instruction = Instruction::new("toggle_if", 1, toggle_if);
+
+/// Instructions the lexer recognises.
+#[derive(Clone, Debug, Default)]
+pub struct InstructionSet {
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `mul`, `do` and `don't`, as in the puzzle.
+    pub fn standard() -> Self {
+        let mut set = InstructionSet::new();
+
+        for instruction in [MUL, DO, DONT] {
+            set.register(instruction);
         }
+
+        set
     }
 
-    None
+    /// The standard set with `add`, `sub`, `toggle` and `toggle_if`.
+    pub fn extended() -> Self {
+        let mut set = InstructionSet::standard();
+
+        for instruction in [ADD, SUB, TOGGLE, TOGGLE_IF] {
+            set.register(instruction);
+        }
+
+        set
+    }
+
+    /// Adds `instruction`, replacing any with the same name.
+    pub fn register(&mut self, instruction: Instruction) {
+        self.instructions
+            .retain(|registered| registered.name != instruction.name);
+        self.instructions.push(instruction);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Instruction> {
+        self.instructions
+            .iter()
+            .find(|instruction| instruction.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instruction> {
+        self.instructions.iter()
+    }
+
+    /// Finder for the `name(` that starts every instruction.
+    pub fn finder(&self) -> KeywordFinder {
+        let keywords: Vec<Vec<u8>> = self
+            .instructions
+            .iter()
+            .map(|instruction| [instruction.name.as_bytes(), b"("].concat())
+            .collect();
+
+        KeywordFinder::new(keywords.iter().map(Vec::as_slice))
+    }
+
+    /// Length of the longest instruction this set can match, with every
+    /// operand at [`MAX_DIGITS`] digits.
+    pub fn max_token_len(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(|instruction| {
+                let commas = instruction.arity.saturating_sub(1);
+                instruction.name.len() + 2 + instruction.arity * MAX_DIGITS + commas
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Instruction found in corrupted memory, with its operands.
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'a> {
+    pub instruction: &'a Instruction,
+    operands: [u32; MAX_ARITY],
+}
+
+impl Token<'_> {
+    pub fn name(&self) -> &'static str {
+        self.instruction.name
+    }
+
+    pub fn operands(&self) -> &[u32] {
+        &self.operands[..self.instruction.arity]
+    }
+}
+
+/// Token with the byte offset where it starts.
+#[derive(Clone, Copy, Debug)]
+pub struct Spanned<'a> {
+    pub offset: usize,
+    pub token: Token<'a>,
+}
+
+/// Finds the well-formed instructions of a set in corrupted memory and skips
+/// everything else. Operands have 1 to [`MAX_DIGITS`] digits.
+pub struct Lexer<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    set: &'a InstructionSet,
+    finder: KeywordFinder,
+}
+
+fn literal(bytes: &[u8], at: usize, literal: &[u8]) -> Option<usize> {
+    bytes[at..]
+        .starts_with(literal)
+        .then_some(at + literal.len())
+}
+
+/// Number of 1 to [`MAX_DIGITS`] digits at `at`, and the offset after it.
+fn number(bytes: &[u8], at: usize) -> Option<(u32, usize)> {
+    let digits = bytes[at..]
+        .iter()
+        .take(MAX_DIGITS)
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+
+    if digits == 0 {
+        return None;
+    }
+
+    let value = bytes[at..at + digits]
+        .iter()
+        .fold(0, |value, digit| value * 10 + (digit - b'0') as u32);
+
+    Some((value, at + digits))
 }
 
-fn find_instruction_bodies(raw_chars: &[u8]) -> Vec<&[u8]> {
-    let mut bounds = vec![];
+fn instruction<'s>(
+    bytes: &[u8],
+    at: usize,
+    instruction: &'s Instruction,
+) -> Option<(Token<'s>, usize)> {
+    let mut at = literal(bytes, at, instruction.name.as_bytes())?;
+    at = literal(bytes, at, b"(")?;
 
-    for start in after_keyword_indices(raw_chars, "mul(".as_bytes()) {
-        let rest = &raw_chars[start..];
+    let mut operands = [0; MAX_ARITY];
 
-        if let Some(offset_end) = index_of_closing_paren(rest) {
-            let end = offset_end + start;
-            bounds.push(&raw_chars[start..end]);
-        } else {
-            return bounds;
+    for (index, operand) in operands[..instruction.arity].iter_mut().enumerate() {
+        if index > 0 {
+            at = literal(bytes, at, b",")?;
         }
+
+        (*operand, at) = number(bytes, at)?;
     }
 
-    bounds
+    at = literal(bytes, at, b")")?;
+
+    Some((
+        Token {
+            instruction,
+            operands,
+        },
+        at,
+    ))
 }
 
-fn parse_instruction_body(body: &str) -> Option<(u32, u32)> {
-    let (left, right) = body.split_once(',')?;
+/// Token of `set` starting exactly at `at`, and the offset after it.
+fn token_at<'s>(bytes: &[u8], at: usize, set: &'s InstructionSet) -> Option<(Token<'s>, usize)> {
+    set.iter()
+        .find_map(|candidate| instruction(bytes, at, candidate))
+}
 
-    Some((left.parse().ok()?, right.parse().ok()?))
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str, set: &'a InstructionSet) -> Self {
+        Lexer {
+            bytes: input.as_bytes(),
+            position: 0,
+            set,
+            finder: set.finder(),
+        }
+    }
 }
 
-pub fn part_1(input: &str) -> String {
-    let mut sum = 0;
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Spanned<'a>;
 
-    for body in find_instruction_bodies(input.as_bytes()) {
-        let body = String::from_utf8_lossy(body);
-        let Some((left, right)) = parse_instruction_body(&body) else {
-            continue;
-        };
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((offset, _)) = self.finder.find(self.bytes, self.position) {
+            if let Some((token, end)) = token_at(self.bytes, offset, self.set) {
+                self.position = end;
+                return Some(Spanned { offset, token });
+            }
 
-        sum += left * right;
+            self.position = offset + 1;
+        }
+
+        self.position = self.bytes.len();
+        None
     }
+}
 
-    sum.to_string()
+/// Bytes read at a time by [`Scanner::new`].
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Lexer over an [`io::Read`] that holds at most one buffer of input.
+///
+/// A token can start near the end of the buffer and finish in the next read,
+/// so positions within [`InstructionSet::max_token_len`] of the end are left
+/// for after the next read. Offsets count from the start of the stream.
+pub struct Scanner<'s, R> {
+    reader: R,
+    set: &'s InstructionSet,
+    finder: KeywordFinder,
+    buffer: Vec<u8>,
+    capacity: usize,
+    lookahead: usize,
+    position: usize,
+    /// Stream offset of `buffer[0]`.
+    consumed: usize,
+    eof: bool,
 }
 
-pub fn part_2(input: &str) -> String {
-    let mut raw_chars_filtered = vec![];
-    let adjusted = format!("do(){}", input);
+impl<'s, R: Read> Scanner<'s, R> {
+    pub fn new(reader: R, set: &'s InstructionSet) -> Self {
+        Self::with_capacity(reader, set, BUFFER_SIZE)
+    }
+
+    /// Scanner with a buffer of `capacity` bytes, raised to twice the longest
+    /// token if it is smaller.
+    pub fn with_capacity(reader: R, set: &'s InstructionSet, capacity: usize) -> Self {
+        let lookahead = set.max_token_len().max(1);
+        let capacity = capacity.max(2 * lookahead);
 
-    for part in adjusted.split("don't()") {
-        let Some((_, enabled)) = part.split_once("do()") else {
-            continue;
+        Scanner {
+            reader,
+            set,
+            finder: set.finder(),
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            lookahead,
+            position: 0,
+            consumed: 0,
+            eof: false,
+        }
+    }
+
+    /// Drops the scanned part of the buffer and reads more after the rest.
+    fn refill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.position);
+        self.consumed += self.position;
+        self.position = 0;
+
+        let filled = self.buffer.len();
+        self.buffer.resize(self.capacity, 0);
+
+        let read = loop {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.buffer.truncate(filled);
+                    return Err(error);
+                }
+                Ok(read) => break read,
+            }
         };
 
-        raw_chars_filtered.extend_from_slice(enabled.as_bytes());
+        self.buffer.truncate(filled + read);
+        self.eof = read == 0;
+
+        Ok(())
+    }
+}
+
+impl<'s, R: Read> Iterator for Scanner<'s, R> {
+    type Item = io::Result<Spanned<'s>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Every token starting before `end` lies entirely in the buffer.
+            let end = if self.eof {
+                self.buffer.len()
+            } else {
+                (self.buffer.len() + 1).saturating_sub(self.lookahead)
+            };
+
+            while let Some((offset, _)) = self
+                .finder
+                .find(&self.buffer, self.position)
+                .filter(|(offset, _)| *offset < end)
+            {
+                if let Some((token, after)) = token_at(&self.buffer, offset, self.set) {
+                    self.position = after;
+
+                    return Some(Ok(Spanned {
+                        offset: self.consumed + offset,
+                        token,
+                    }));
+                }
+
+                self.position = offset + 1;
+            }
+
+            self.position = self.position.max(end);
+
+            if self.eof {
+                return None;
+            }
+
+            if let Err(error) = self.refill() {
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Runs instructions, keeping the sum of every result as well as the sum of
+/// the ones produced while enabled.
+pub struct Interpreter {
+    pub enabled: bool,
+    pub all: i64,
+    pub enabled_only: i64,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter {
+            enabled: true,
+            all: 0,
+            enabled_only: 0,
+        }
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the result of an instruction to the sums.
+    pub fn accumulate(&mut self, value: i64) {
+        self.all += value;
+
+        if self.enabled {
+            self.enabled_only += value;
+        }
+    }
+
+    pub fn execute(&mut self, token: &Token) {
+        (token.instruction.semantics)(self, token.operands());
+    }
+
+    /// Runs every instruction of `set` read from `reader`, in one pass.
+    pub fn run_reader<R: Read>(reader: R, set: &InstructionSet) -> io::Result<Self> {
+        let mut interpreter = Interpreter::new();
+
+        for spanned in Scanner::new(reader, set) {
+            interpreter.execute(&spanned?.token);
+        }
+
+        Ok(interpreter)
+    }
+
+    /// Runs every instruction of `set` found in `input`, in one pass.
+    pub fn run(input: &str, set: &InstructionSet) -> Self {
+        let mut interpreter = Interpreter::new();
+
+        for spanned in Lexer::new(input, set) {
+            interpreter.execute(&spanned.token);
+        }
+
+        interpreter
     }
+}
+
+pub fn part_1(input: &str) -> String {
+    Interpreter::run(input, &InstructionSet::standard())
+        .all
+        .to_string()
+}
 
-    let input = String::from_utf8_lossy(&raw_chars_filtered);
+pub fn part_2(input: &str) -> String {
+    Interpreter::run(input, &InstructionSet::standard())
+        .enabled_only
+        .to_string()
+}
+
+pub fn part_1_streaming(input: &str) -> String {
+    Interpreter::run_reader(input.as_bytes(), &InstructionSet::standard())
+        .unwrap()
+        .all
+        .to_string()
+}
 
-    part_1(&input)
+pub fn part_2_streaming(input: &str) -> String {
+    Interpreter::run_reader(input.as_bytes(), &InstructionSet::standard())
+        .unwrap()
+        .enabled_only
+        .to_string()
 }
 
 #[cfg(test)]
 mod test {
+    use std::io::{self, Read};
+
     use crate::{
-        day03::{part_1, part_2},
+        day03::{
+            part_1, part_2, Instruction, InstructionSet, Interpreter, Lexer, Scanner, Spanned,
+        },
         input, output,
     };
 
@@ -101,4 +494,118 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_03), output::DAY_03_2)
     }
+
+    fn tokens(input: &str, set: &InstructionSet) -> Vec<(usize, &'static str, Vec<u32>)> {
+        Lexer::new(input, set)
+            .map(|spanned| {
+                let token = spanned.token;
+                (spanned.offset, token.name(), token.operands().to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lexer_test() {
+        let input = "mul(1,23)don't()xmul(1234,5)mul(12,3456)mul( 1,2)do()mul(999,0)";
+
+        assert_eq!(
+            tokens(input, &InstructionSet::standard()),
+            vec![
+                (0, "mul", vec![1, 23]),
+                (9, "don't", vec![]),
+                (49, "do", vec![]),
+                (53, "mul", vec![999, 0]),
+            ]
+        );
+    }
+
+    /// Hands out at most `step` bytes per read.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let count = self.step.min(buffer.len()).min(self.bytes.len());
+            buffer[..count].copy_from_slice(&self.bytes[..count]);
+            self.bytes = &self.bytes[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn scanner_matches_lexer() {
+        for set in [InstructionSet::standard(), InstructionSet::extended()] {
+            let expected = tokens(input::DAY_03, &set);
+
+            for (capacity, step) in [(0, 1), (0, 7), (40, 3), (1000, 1000)] {
+                let reader = Trickle {
+                    bytes: input::DAY_03.as_bytes(),
+                    step,
+                };
+                let scanned: Vec<_> = Scanner::with_capacity(reader, &set, capacity)
+                    .map(|spanned| {
+                        let Spanned { offset, token } = spanned.unwrap();
+                        (offset, token.name(), token.operands().to_vec())
+                    })
+                    .collect();
+
+                assert_eq!(scanned, expected, "capacity {} step {}", capacity, step);
+            }
+        }
+    }
+
+    #[test]
+    fn extended_test() {
+        let set = InstructionSet::extended();
+        let input = "add(2,3)]sub(1,10)toggle()mul(4,4)toggle_if(0)add(1,1,1)toggle_if(7)mul(2,2)";
+
+        assert_eq!(
+            tokens(input, &set)
+                .iter()
+                .map(|(_, name, _)| *name)
+                .collect::<Vec<_>>(),
+            vec![
+                "add",
+                "sub",
+                "toggle",
+                "mul",
+                "toggle_if",
+                "toggle_if",
+                "mul"
+            ]
+        );
+
+        let interpreter = Interpreter::run(input, &set);
+        assert_eq!(interpreter.all, 5 - 9 + 16 + 4);
+        assert_eq!(interpreter.enabled_only, 5 - 9 + 4);
+        assert!(interpreter.enabled);
+
+        let mut set = InstructionSet::standard();
+        set.register(Instruction::new("mul", 3, |interpreter, operands| {
+            interpreter.accumulate(operands.iter().map(|&operand| operand as i64).product())
+        }));
+        assert_eq!(Interpreter::run("mul(2,3)mul(2,3,4)", &set).all, 24);
+        assert!(set.get("mul").is_some_and(|mul| mul.arity == 3));
+    }
+
+    #[test]
+    fn short_name_test() {
+        let mut set = InstructionSet::standard();
+        set.register(Instruction::new("", 2, |interpreter, operands| {
+            interpreter.accumulate(operands[0] as i64 - operands[1] as i64)
+        }));
+        let input = "mul(2,3)(9,4)xm(1,1)";
+
+        assert_eq!(
+            tokens(input, &set),
+            vec![
+                (0, "mul", vec![2, 3]),
+                (8, "", vec![9, 4]),
+                (15, "", vec![1, 1])
+            ]
+        );
+        assert_eq!(Interpreter::run(input, &set).all, 6 + 5);
+    }
 }
@@ -1,75 +1,121 @@
-fn diag_down(x: usize, y: usize, height: usize) -> usize {
-    x + height - y
-}
+use std::fmt::Display;
 
-fn diag_up(x: usize, y: usize) -> usize {
-    x + y
-}
+use crate::grid::{GridMap, GridSet, Heading, Position};
 
-fn count_xmas(value: &str) -> usize {
-    let mut xmas = 0;
+/// Word found in the grid, read from `start` towards `heading`.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Match {
+    pub start: Position,
+    pub heading: Heading,
+    pub len: usize,
+}
 
-    for window in value.as_bytes().windows(4) {
-        if window == "XMAS".as_bytes() {
-            xmas += 1;
-        }
-        if window == "SAMX".as_bytes() {
-            xmas += 1;
-        }
+impl Match {
+    /// Cells of the word, from its first letter to its last.
+    pub fn cells(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.len).map(|steps| self.start.step(self.heading, steps).unwrap())
     }
-
-    xmas
 }
 
-fn count_many(values: &Vec<String>) -> usize {
-    let mut xmas = 0;
+/// Letter grid that can be searched for any word in any directions.
+pub struct WordSearch {
+    grid: GridMap<char>,
+}
 
-    for value in values {
-        xmas += count_xmas(value)
+impl WordSearch {
+    pub fn new(input: &str) -> Self {
+        WordSearch {
+            grid: GridMap::parse(input, |c| c),
+        }
     }
 
-    xmas
-}
-
-pub fn part_1(input: &str) -> String {
-    type Strings = Vec<String>;
+    fn matches_at(&self, word: &[char], start: Position, heading: Heading) -> bool {
+        word.iter().enumerate().all(|(steps, letter)| {
+            start
+                .step(heading, steps)
+                .and_then(|position| self.grid.get(&position))
+                == Some(letter)
+        })
+    }
 
-    let rows: Strings = input.lines().map(|line| line.to_owned()).collect();
-    let mut columns: Strings = vec![];
-    let mut downward: Strings = vec![];
-    let mut upward: Strings = vec![];
+    /// Every occurrence of `word` read towards one of `headings`, in
+    /// row-major order of their first letter and then in the order of
+    /// `headings`.
+    pub fn find(&self, word: &str, headings: &[Heading]) -> Vec<Match> {
+        let word: Vec<char> = word.chars().collect();
+        let mut matches = vec![];
 
-    for (y, line) in rows.iter().enumerate() {
-        for (x, character) in line.chars().enumerate() {
-            let d = diag_down(x, y, rows.len());
-            let u = diag_up(x, y);
+        let Some(first) = word.first() else {
+            return matches;
+        };
 
-            while x >= columns.len() {
-                columns.push(String::new())
+        for (start, letter) in self.grid.iter() {
+            if letter != first {
+                continue;
             }
 
-            while d >= downward.len() {
-                downward.push(String::new());
+            for &heading in headings {
+                if self.matches_at(&word, start, heading) {
+                    matches.push(Match {
+                        start,
+                        heading,
+                        len: word.len(),
+                    });
+                }
             }
+        }
 
-            while u >= upward.len() {
-                upward.push(String::new());
+        matches
+    }
+
+    /// The grid with every letter outside `matches` replaced by `.`.
+    pub fn render(&self, matches: &[Match]) -> Rendered<'_> {
+        let mut shown = GridSet::new(self.grid.width(), self.grid.height());
+
+        for found in matches {
+            for position in found.cells() {
+                shown.insert(position);
             }
+        }
 
-            columns[x].push(character);
-            downward[d].push(character);
-            upward[u].push(character);
+        Rendered {
+            grid: &self.grid,
+            shown,
         }
     }
+}
+
+/// Grid showing only matched letters, as made by [`WordSearch::render`].
+pub struct Rendered<'a> {
+    grid: &'a GridMap<char>,
+    shown: GridSet,
+}
+
+impl Display for Rendered<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                let position = Position::new(x, y);
+
+                if self.shown.contains(&position) {
+                    write!(f, "{}", self.grid[position])?;
+                } else {
+                    write!(f, ".")?;
+                }
+            }
 
-    let mut xmas = 0;
+            writeln!(f)?;
+        }
 
-    xmas += count_many(&rows);
-    xmas += count_many(&columns);
-    xmas += count_many(&downward);
-    xmas += count_many(&upward);
+        Ok(())
+    }
+}
 
-    xmas.to_string()
+pub fn part_1(input: &str) -> String {
+    WordSearch::new(input)
+        .find("XMAS", &Heading::ALL)
+        .len()
+        .to_string()
 }
 
 fn outer_match(first: u8, second: u8) -> bool {
@@ -133,7 +179,8 @@ pub fn part_2(input: &str) -> String {
 #[cfg(test)]
 mod test {
     use crate::{
-        day04::{part_1, part_2},
+        day04::{part_1, part_2, Match, WordSearch},
+        grid::{Heading, Position},
         input, output,
     };
 
@@ -154,4 +201,44 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_04), output::DAY_04_2)
     }
+
+    #[test]
+    fn word_search_test() {
+        let search = WordSearch::new("XMAS\nMM.A\nA.A.\nSXMS\n");
+
+        assert_eq!(
+            search.find("XMAS", &Heading::ALL),
+            vec![
+                Match {
+                    start: Position::new(0, 0),
+                    heading: Heading::East,
+                    len: 4
+                },
+                Match {
+                    start: Position::new(0, 0),
+                    heading: Heading::SouthEast,
+                    len: 4
+                },
+                Match {
+                    start: Position::new(0, 0),
+                    heading: Heading::South,
+                    len: 4
+                },
+            ]
+        );
+
+        let east = search.find("XMAS", &[Heading::East]);
+        assert_eq!(east.len(), 1);
+        assert_eq!(search.render(&east).to_string(), "XMAS\n....\n....\n....\n");
+
+        let reversed = search.find("SAMX", &[Heading::North, Heading::West]);
+        assert_eq!(reversed.len(), 2);
+        assert_eq!(
+            search.render(&reversed).to_string(),
+            "XMAS\nM...\nA...\nS...\n"
+        );
+
+        assert!(search.find("", &Heading::ALL).is_empty());
+        assert!(search.find("XMAS", &[]).is_empty());
+    }
 }
@@ -1,12 +1,14 @@
 use std::collections::{HashMap, HashSet};
 
-type Rules = HashMap<u32, Vec<u32>>;
+use crate::cancel;
+
+pub type Rules = HashMap<u32, Vec<u32>>;
 
 fn add_page_rule(rules: &mut Rules, page: u32, dependency: u32) {
     rules.entry(page).or_default().push(dependency);
 }
 
-fn valid_update(update: &[u32], rules: &Rules) -> bool {
+pub fn valid_update(update: &[u32], rules: &Rules) -> bool {
     let mut printed: HashSet<u32> = HashSet::new();
 
     for page in update {
@@ -61,7 +63,18 @@ pub fn part_1(input: &str) -> String {
     sum.to_string()
 }
 
-fn print_with_rules(page: &u32, update: &[u32], rules: &Rules, print: &mut Vec<u32>) {
+/// Prints the dependencies of `page` before `page` itself. `visiting` holds
+/// the pages whose dependencies are being printed, so a page that depends on
+/// itself through the rules is reported instead of recursing forever.
+fn print_with_rules(
+    page: &u32,
+    update: &[u32],
+    rules: &Rules,
+    print: &mut Vec<u32>,
+    visiting: &mut Vec<u32>,
+) {
+    cancel::checkpoint();
+
     let Some(rule) = rules.get(page) else {
         if !print.contains(page) {
             print.push(*page);
@@ -69,23 +82,31 @@ fn print_with_rules(page: &u32, update: &[u32], rules: &Rules, print: &mut Vec<u
         return;
     };
 
+    visiting.push(*page);
+
     for dependency in rule {
+        if visiting.contains(dependency) {
+            panic!("rules for pages {:?} form a cycle", visiting);
+        }
+
         if update.contains(dependency) && !print.contains(dependency) {
-            print_with_rules(dependency, update, rules, print);
+            print_with_rules(dependency, update, rules, print, visiting);
         }
     }
 
+    visiting.pop();
+
     if !print.contains(page) {
         print.push(*page);
     }
 }
 
-fn re_order(update: &[u32], rules: &Rules) -> Vec<u32> {
+pub fn re_order(update: &[u32], rules: &Rules) -> Vec<u32> {
     let mut print: Vec<u32> = vec![];
 
     for page in update {
         if !print.contains(page) {
-            print_with_rules(page, update, rules, &mut print);
+            print_with_rules(page, update, rules, &mut print, &mut vec![]);
         }
     }
 
@@ -130,9 +151,12 @@ pub fn part_2(input: &str) -> String {
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use crate::{
         day05::{part_1, part_2},
         input, output,
+        runner::{run_part, Outcome, RunOptions},
     };
 
     #[test]
@@ -152,4 +176,15 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_05), output::DAY_05_2)
     }
+
+    #[test]
+    fn cyclic_rules_test() {
+        let options = RunOptions {
+            timeout: Some(Duration::from_secs(5)),
+            ..RunOptions::default()
+        };
+        let outcome = run_part(part_2, "1|2\n2|1\n\n2,1\n", &options);
+
+        assert!(matches!(outcome, Outcome::Panicked(message) if message.contains("cycle")));
+    }
 }
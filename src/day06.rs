@@ -1,176 +1,112 @@
-use core::panic;
 use rayon::prelude::*;
-use std::{collections::HashSet, fmt::Display};
-
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
+use std::fmt::Display;
+
+use crate::{
+    cancel,
+    cycle::History,
+    grid::{Direction, GridMap, GridSet, Position},
+    progress::Progress,
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Tile {
+    Floor,
+    Obstruction,
+    Start,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Hash)]
-struct Position {
-    x: usize,
-    y: usize,
+/// The map of the lab, shared by every guard walking it.
+pub struct Lab {
+    map: GridMap<Tile>,
+    start: Position,
 }
 
-impl Position {
-    fn up(&self) -> Self {
-        Position {
-            x: self.x,
-            y: self.y - 1,
-        }
-    }
-
-    fn down(&self) -> Self {
-        Position {
-            x: self.x,
-            y: self.y + 1,
-        }
-    }
-
-    fn left(&self) -> Self {
-        Position {
-            x: self.x - 1,
-            y: self.y,
-        }
-    }
-
-    fn right(&self) -> Self {
-        Position {
-            x: self.x + 1,
-            y: self.y,
-        }
+impl From<&str> for Lab {
+    fn from(input: &str) -> Self {
+        let map = GridMap::parse(input, |character| match character {
+            '.' => Tile::Floor,
+            '#' => Tile::Obstruction,
+            '^' => Tile::Start,
+            _ => panic!("encountered not map character {} in map", character),
+        });
+
+        let start = map
+            .iter()
+            .find(|(_, tile)| **tile == Tile::Start)
+            .map(|(position, _)| position)
+            .unwrap_or(Position { x: 0, y: 0 });
+
+        Lab { map, start }
     }
 }
 
+/// One walk through a [`Lab`]. Only the state of the walk is owned, so a
+/// guard is cheap to create for every candidate obstruction.
 #[derive(Clone)]
-struct Guard {
-    width: usize,
-    height: usize,
+pub struct Guard<'a> {
+    lab: &'a Lab,
     position: Position,
-    obstructions: HashSet<Position>,
     direction: Direction,
-    visited: HashSet<Position>,
-    history: Vec<(Position, Direction)>,
-    turns: HashSet<Position>,
+    visited: GridSet,
+    history: History<(Position, Direction)>,
+    turns: GridSet,
     in_loop: bool,
     extra_obstruction: Option<Position>,
 }
 
-impl From<&str> for Guard {
-    fn from(input: &str) -> Self {
-        let mut width = 0;
-        let mut obstructions = HashSet::new();
-        let mut position = Position { x: 0, y: 0 };
-        let mut visited = HashSet::new();
-        let mut history = vec![];
-        let turns = HashSet::new();
-        let mut height = 0;
+impl<'a> Guard<'a> {
+    pub fn new(lab: &'a Lab) -> Self {
+        let (width, height) = (lab.map.width(), lab.map.height());
+        let position = lab.start;
         let direction = Direction::Up;
-        let in_loop = false;
-        let extra_obstruction = None;
-
-        for (y, line) in input.lines().enumerate() {
-            if width == 0 {
-                width = line.len();
-            } else {
-                assert!(width == line.len());
-            }
-
-            for (x, character) in line.chars().enumerate() {
-                match character {
-                    '.' => {}
-                    '#' => {
-                        obstructions.insert(Position { x, y });
-                    }
-                    '^' => {
-                        position = Position { x, y };
-                    }
-                    _ => {
-                        panic!("encountered not map character {} in map", character);
-                    }
-                }
-            }
-            height += 1;
-        }
+        let mut visited = GridSet::new(width, height);
+        let mut history = History::new();
 
         visited.insert(position);
-        history.push((position, direction));
+        history.record((position, direction));
 
         Guard {
-            width,
-            height,
-            obstructions,
+            lab,
             position,
-            direction: Direction::Up,
+            direction,
             visited,
             history,
-            turns,
-            in_loop,
-            extra_obstruction,
-        }
-    }
-}
-
-impl Guard {
-    fn up(&self) -> Option<Position> {
-        if self.position.y == 0 {
-            return None;
-        }
-
-        Some(self.position.up())
-    }
-
-    fn down(&self) -> Option<Position> {
-        if self.position.y + 1 >= self.height {
-            return None;
+            turns: GridSet::new(width, height),
+            in_loop: false,
+            extra_obstruction: None,
         }
-
-        Some(self.position.down())
     }
 
-    fn left(&self) -> Option<Position> {
-        if self.position.x == 0 {
-            return None;
+    /// A guard in a lab with one more obstruction at `position`.
+    pub fn with_obstruction(lab: &'a Lab, position: Position) -> Self {
+        Guard {
+            extra_obstruction: Some(position),
+            ..Guard::new(lab)
         }
-
-        Some(self.position.left())
     }
 
-    fn right(&self) -> Option<Position> {
-        if self.position.x + 1 >= self.width {
-            return None;
-        }
-
-        Some(self.position.right())
+    fn obstructed(&self, position: &Position) -> bool {
+        self.extra_obstruction == Some(*position)
+            || self.lab.map.get(position) == Some(&Tile::Obstruction)
     }
 
     fn turn(&mut self) {
-        self.direction = match self.direction {
-            Direction::Left => Direction::Up,
-            Direction::Right => Direction::Down,
-            Direction::Up => Direction::Right,
-            Direction::Down => Direction::Left,
-        }
+        self.direction = self.direction.turn_right();
     }
 
-    fn patrol(&mut self) {
-        while self.step() {}
+    pub fn patrol(&mut self) {
+        while self.step() {
+            cancel::checkpoint();
+        }
     }
 
     fn forward(&self) -> Option<Position> {
-        match self.direction {
-            Direction::Left => self.left(),
-            Direction::Right => self.right(),
-            Direction::Up => self.up(),
-            Direction::Down => self.down(),
-        }
+        self.position
+            .step(self.direction.heading(), 1)
+            .filter(|forward| self.lab.map.get(forward).is_some())
     }
 
-    fn step(&mut self) -> bool {
+    pub fn step(&mut self) -> bool {
         let Some(forward) = self.forward() else {
             return false;
         };
@@ -180,7 +116,7 @@ impl Guard {
             return false;
         };
 
-        if self.obstructions.contains(&forward) {
+        if self.obstructed(&forward) {
             self.turns.insert(self.position);
             self.turn();
             return self.step();
@@ -189,16 +125,30 @@ impl Guard {
         self.position = forward;
 
         self.visited.insert(self.position);
-        self.history.push((self.position, self.direction));
+        self.history.record((self.position, self.direction));
 
         true
     }
 }
 
-impl Display for Guard {
+impl Guard<'_> {
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn visited(&self) -> &GridSet {
+        &self.visited
+    }
+
+    pub fn in_loop(&self) -> bool {
+        self.in_loop
+    }
+}
+
+impl Display for Guard<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
+        for y in 0..self.lab.map.height() {
+            for x in 0..self.lab.map.width() {
                 let position = Position { x, y };
 
                 if self.extra_obstruction.eq(&Some(position)) {
@@ -206,7 +156,7 @@ impl Display for Guard {
                     continue;
                 }
 
-                if self.obstructions.contains(&position) {
+                if self.lab.map[position] == Tile::Obstruction {
                     write!(f, "#")?;
                     continue;
                 }
@@ -222,7 +172,7 @@ impl Display for Guard {
                     let mut up = false;
                     let mut down = false;
 
-                    self.history.clone().into_iter().for_each(|(p, direction)| {
+                    self.history.states().for_each(|(p, direction)| {
                         if p.eq(&position) {
                             match direction {
                                 Direction::Left => left = true,
@@ -258,36 +208,39 @@ impl Display for Guard {
 }
 
 pub fn part_1(input: &str) -> String {
-    let mut guard = Guard::from(input);
+    let lab = Lab::from(input);
+    let mut guard = Guard::new(&lab);
 
     guard.patrol();
+    crate::snapshot!("patrol", guard);
 
     guard.visited.len().to_string()
 }
 
 pub fn part_2(input: &str) -> String {
-    let mut patrol_guard = Guard::from(input);
-    let guard_at_start = patrol_guard.clone();
-    let mut obstructions = HashSet::new();
-    let mut guards = vec![];
+    let lab = Lab::from(input);
+    let mut patrol_guard = Guard::new(&lab);
+    let mut obstructions = GridSet::new(lab.map.width(), lab.map.height());
+    let mut candidates = vec![];
 
     while patrol_guard.step() {
-        if !obstructions.insert(patrol_guard.position) {
-            continue;
+        if obstructions.insert(patrol_guard.position) {
+            candidates.push(patrol_guard.position);
         }
-
-        let mut possible_guard = guard_at_start.clone();
-        possible_guard.obstructions.insert(patrol_guard.position);
-        possible_guard.extra_obstruction = Some(patrol_guard.position);
-
-        guards.push(possible_guard);
     }
 
-    let loops: usize = guards
-        .par_iter_mut()
-        .map(|guard| {
+    crate::debug!("checking {} candidate obstructions", candidates.len());
+    let progress = Progress::new("obstructions", candidates.len());
+
+    let loops: usize = candidates
+        .par_iter()
+        .map(|&obstruction| {
+            let mut guard = Guard::with_obstruction(&lab, obstruction);
             guard.patrol();
+            progress.inc(1);
+
             if guard.in_loop {
+                crate::trace!("loop with obstruction at {:?}", guard.extra_obstruction);
                 return 1;
             }
 
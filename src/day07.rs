@@ -1,15 +1,17 @@
 use rayon::{iter::ParallelIterator, str::ParallelString};
 
+use crate::math::{checked_concat, digit_count, split_digits};
+
 #[derive(Debug)]
-struct Equation {
-    test: usize,
-    numbers: Vec<usize>,
+pub struct Equation {
+    pub test: u64,
+    pub numbers: Vec<u64>,
 }
 
 impl From<&str> for Equation {
     fn from(value: &str) -> Self {
         let (test_str, rest) = value.split_once(':').unwrap();
-        let test: usize = test_str.parse().unwrap();
+        let test: u64 = test_str.parse().unwrap();
         let numbers = rest
             .split_whitespace()
             .flat_map(|value| value.parse())
@@ -20,67 +22,86 @@ impl From<&str> for Equation {
 }
 
 impl Equation {
-    fn valid(&self, include_concat: bool) -> bool {
-        let mut count = 0;
-        matches(&self.numbers, self.test, include_concat, &mut count);
-        count > 0
+    pub fn valid(&self, include_concat: bool) -> bool {
+        let Some((first, rest)) = self.numbers.split_first() else {
+            return false;
+        };
+
+        matches(rest, *first, self.test, include_concat) > 0
     }
-}
 
-fn concat(lhs: usize, rhs: usize) -> usize {
-    let mag = (rhs as f64).log10().floor() as usize + 1;
-    lhs * 10_usize.pow(mag as u32) + rhs
+    /// Same answer as [`Equation::valid`], but works back from `test` by
+    /// undoing the last operator first. Divisibility and digit suffixes rule
+    /// out most branches immediately.
+    pub fn valid_reverse(&self, include_concat: bool) -> bool {
+        unmatches(&self.numbers, self.test, include_concat)
+    }
 }
 
-fn matches(numbers: &[usize], test: usize, include_concat: bool, count: &mut usize) {
-    if numbers.is_empty() {
-        return;
+/// Number of operator choices that turn `acc` followed by `numbers` into `test`.
+fn matches(numbers: &[u64], acc: u64, test: u64, include_concat: bool) -> usize {
+    let Some((next, rest)) = numbers.split_first() else {
+        return usize::from(acc == test);
+    };
+
+    if acc > test {
+        return 0;
     }
 
-    if numbers.len() == 1 {
-        if numbers[0] == test {
-            *count += 1;
-        }
-        return;
+    let mut results = [acc.checked_add(*next), acc.checked_mul(*next), None];
+
+    if include_concat {
+        results[2] = checked_concat(acc, *next);
     }
 
-    let lhs = numbers[0];
-    let rhs = numbers[1];
-    let add = lhs + rhs;
-    let mul = lhs * rhs;
+    // An overflowed result is already past any `u64` test value.
+    results
+        .into_iter()
+        .flatten()
+        .map(|result| matches(rest, result, test, include_concat))
+        .sum()
+}
 
-    if add <= test {
-        let add_branch: Vec<usize> = std::iter::once(add)
-            .chain(numbers[2..].iter().copied())
-            .collect();
-        matches(&add_branch, test, include_concat, count);
+/// Whether `numbers` can be combined into `target`, peeling numbers off the
+/// end.
+fn unmatches(numbers: &[u64], target: u64, include_concat: bool) -> bool {
+    let Some((last, rest)) = numbers.split_last() else {
+        return false;
+    };
+
+    if rest.is_empty() {
+        return *last == target;
     }
-    if mul <= test {
-        let mul_branch: Vec<usize> = std::iter::once(mul)
-            .chain(numbers[2..].iter().copied())
-            .collect();
-        matches(&mul_branch, test, include_concat, count);
+
+    if target >= *last && unmatches(rest, target - last, include_concat) {
+        return true;
     }
 
-    if include_concat {
-        let con = concat(lhs, rhs);
+    if *last == 0 {
+        if target == 0 {
+            return true;
+        }
+    } else if target.is_multiple_of(*last) && unmatches(rest, target / last, include_concat) {
+        return true;
+    }
 
-        if con <= test {
-            let con_branch: Vec<usize> = std::iter::once(con)
-                .chain(numbers[2..].iter().copied())
-                .collect();
+    if include_concat {
+        let (head, tail) = split_digits(target, digit_count(*last));
 
-            matches(&con_branch, test, include_concat, count);
+        if tail == *last && unmatches(rest, head, include_concat) {
+            return true;
         }
     }
+
+    false
 }
 
-pub fn part_1(input: &str) -> String {
-    let sum: usize = input
+fn calibration(input: &str, valid: impl Fn(&Equation) -> bool + Sync) -> String {
+    let sum: u64 = input
         .par_lines()
         .map(|line| {
             let equation = Equation::from(line);
-            if equation.valid(false) {
+            if valid(&equation) {
                 return equation.test;
             }
 
@@ -90,27 +111,30 @@ pub fn part_1(input: &str) -> String {
 
     sum.to_string()
 }
+
+pub fn part_1(input: &str) -> String {
+    calibration(input, |equation| equation.valid(false))
+}
+
 pub fn part_2(input: &str) -> String {
-    let sum: usize = input
-        .par_lines()
-        .map(|line| {
-            let equation = Equation::from(line);
-            if equation.valid(true) {
-                return equation.test;
-            }
+    calibration(input, |equation| equation.valid(true))
+}
 
-            0
-        })
-        .sum();
+pub fn part_1_reverse(input: &str) -> String {
+    calibration(input, |equation| equation.valid_reverse(false))
+}
 
-    sum.to_string()
+pub fn part_2_reverse(input: &str) -> String {
+    calibration(input, |equation| equation.valid_reverse(true))
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        day07::{concat, part_1, part_2},
-        input, output,
+        day07::{part_1, part_1_reverse, part_2, part_2_reverse, Equation},
+        input,
+        math::checked_concat,
+        output,
     };
 
     #[test]
@@ -125,7 +149,26 @@ mod test {
 
     #[test]
     fn concat_test() {
-        assert_eq!(12345usize, concat(12, 345));
+        assert_eq!(Some(12345), checked_concat(12, 345));
+    }
+
+    #[test]
+    fn overflow_test() {
+        let equation = Equation {
+            test: u64::MAX,
+            numbers: vec![u64::MAX / 2, 3, 2, 1],
+        };
+
+        assert!(!equation.valid(true));
+        assert_eq!(equation.valid(true), equation.valid_reverse(true));
+
+        let equation = Equation {
+            test: u64::MAX,
+            numbers: vec![u64::MAX / 2, 2, 1],
+        };
+
+        assert!(equation.valid(false));
+        assert!(equation.valid_reverse(false));
     }
 
     #[test]
@@ -136,4 +179,22 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_07), output::DAY_07_2)
     }
+
+    #[test]
+    fn reverse_example() {
+        assert_eq!(
+            part_1_reverse(input::example::DAY_07),
+            output::example::DAY_07_1
+        );
+        assert_eq!(
+            part_2_reverse(input::example::DAY_07),
+            output::example::DAY_07_2
+        );
+    }
+
+    #[test]
+    fn reverse_real() {
+        assert_eq!(part_1_reverse(input::DAY_07), output::DAY_07_1);
+        assert_eq!(part_2_reverse(input::DAY_07), output::DAY_07_2);
+    }
 }
@@ -1,27 +1,13 @@
-use std::{collections::HashSet, fmt::Display, isize};
+use std::fmt::Display;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-struct Position {
-    x: usize,
-    y: usize,
-}
-
-impl Position {
-    fn dist(self, other: Position) -> (isize, isize) {
-        let x = self.x as isize - other.x as isize;
-        let y = self.y as isize - other.y as isize;
-
-        (x, y)
-    }
-}
+use crate::grid::{GridMap, GridSet, Position};
 
-type Antenna = (Position, char);
+pub type Antenna = (Position, char);
 
-struct Map {
-    width: usize,
-    height: usize,
+pub struct Map {
+    grid: GridMap<char>,
     antennas: Vec<Antenna>,
-    antinodes: HashSet<Position>,
+    antinodes: GridSet,
 }
 
 impl Map {
@@ -36,11 +22,10 @@ impl Map {
             let sx = second.x as isize - dx * iteration;
             let sy = second.y as isize - dy * iteration;
 
-            let first_within_bounds =
-                fx >= 0 && fx < self.width as isize && fy >= 0 && fy < self.height as isize;
+            let (width, height) = (self.grid.width() as isize, self.grid.height() as isize);
 
-            let second_within_bounds =
-                sx >= 0 && sx < self.width as isize && sy >= 0 && sy < self.height as isize;
+            let first_within_bounds = fx >= 0 && fx < width && fy >= 0 && fy < height;
+            let second_within_bounds = sx >= 0 && sx < width && sy >= 0 && sy < height;
 
             let within_bounds = first_within_bounds || second_within_bounds;
 
@@ -90,38 +75,46 @@ impl Map {
         self.antennas.push(antenna);
     }
 
-    fn from_str(input: &str, equal_dist: bool) -> Self {
-        let line_data: Vec<&str> = input.lines().collect();
+    pub fn from_str(input: &str, equal_dist: bool) -> Self {
+        let grid = GridMap::parse(input, |char| char);
+        let antennas: Vec<Antenna> = grid
+            .iter()
+            .filter(|(_, char)| char.is_ascii_alphanumeric())
+            .map(|(position, char)| (position, *char))
+            .collect();
 
         let mut map = Map {
-            width: line_data.first().map(|line| line.len()).unwrap_or(0),
-            height: line_data.len(),
             antennas: vec![],
-            antinodes: HashSet::new(),
+            antinodes: GridSet::new(grid.width(), grid.height()),
+            grid,
         };
 
-        for (y, line) in line_data.into_iter().enumerate() {
-            for (x, char) in line.chars().enumerate() {
-                let position = Position { x, y };
-
-                if char.is_ascii_alphanumeric() {
-                    map.add_antenna((position, char), equal_dist);
-                }
-            }
+        for antenna in antennas {
+            map.add_antenna(antenna, equal_dist);
         }
 
         map
     }
 }
 
+impl Map {
+    pub fn antennas(&self) -> &[Antenna] {
+        &self.antennas
+    }
+
+    pub fn antinodes(&self) -> &GridSet {
+        &self.antinodes
+    }
+}
+
 impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
                 let position = Position { x, y };
+                let char = self.grid[position];
 
-                if let Some((_, char)) = self.antennas.iter().find(|(pos, _char)| position.eq(pos))
-                {
+                if char.is_ascii_alphanumeric() {
                     write!(f, "{}", char)?;
                     continue;
                 }
@@ -143,11 +136,13 @@ impl Display for Map {
 
 pub fn part_1(input: &str) -> String {
     let map = Map::from_str(input, true);
+    crate::snapshot!("antinodes", map);
 
     map.antinodes.len().to_string()
 }
 pub fn part_2(input: &str) -> String {
     let map = Map::from_str(input, false);
+    crate::snapshot!("antinodes", map);
 
     map.antinodes.len().to_string()
 }
@@ -1,13 +1,15 @@
-use std::{collections::HashSet, fmt::Display};
+use std::{fmt::Display, ops::Range};
 
-type DiskBlock = Option<usize>;
+use crate::{cancel, range_set::RangeSet};
 
-struct DiskMap {
+pub type DiskBlock = Option<usize>;
+
+pub struct DiskMap {
     blocks: Vec<DiskBlock>,
 }
 
 impl DiskMap {
-    fn new(layout: &str) -> DiskMap {
+    pub fn new(layout: &str) -> DiskMap {
         let mut map = DiskMap { blocks: vec![] };
 
         let bytes = layout.trim().as_bytes();
@@ -23,7 +25,7 @@ impl DiskMap {
         map
     }
 
-    fn compact(&mut self) {
+    pub fn compact(&mut self) {
         let mut data_end = self.blocks.len();
 
         for free_space_index in 0..self.blocks.len() {
@@ -79,11 +81,13 @@ impl DiskMap {
         }
     }
 
-    fn compact_2(&mut self) {
+    pub fn compact_2(&mut self) {
         let mut file_end = self.blocks.len() - 1;
         let mut max_file_id = usize::MAX;
 
         while file_end > 0 {
+            cancel::checkpoint();
+
             let Some(file_id) = self.blocks[file_end] else {
                 file_end -= 1;
                 continue;
@@ -162,7 +166,11 @@ impl DiskMap {
         // }
     }
 
-    fn checksum(&self) -> usize {
+    pub fn blocks(&self) -> &[DiskBlock] {
+        &self.blocks
+    }
+
+    pub fn checksum(&self) -> usize {
         let mut checksum = 0;
         for (index, block) in self.blocks.iter().enumerate() {
             match block {
@@ -197,24 +205,147 @@ impl Display for DiskMap {
     }
 }
 
+/// Disk described by the span each file occupies and the set of free spans,
+/// instead of one entry per block.
+pub struct SpanDiskMap {
+    files: Vec<Range<usize>>,
+    free: RangeSet,
+}
+
+impl SpanDiskMap {
+    pub fn new(layout: &str) -> SpanDiskMap {
+        let mut map = SpanDiskMap {
+            files: vec![],
+            free: RangeSet::new(),
+        };
+
+        let mut position = 0;
+
+        for chunk in layout.trim().as_bytes().chunks(2) {
+            let count = (chunk[0] - b'0') as usize;
+            let free_space = if chunk.len() == 2 {
+                (chunk[1] - b'0') as usize
+            } else {
+                0
+            };
+
+            map.files.push(position..position + count);
+            position += count;
+
+            map.free.insert(position..position + free_space);
+            position += free_space;
+        }
+
+        map
+    }
+
+    /// Span of each file, indexed by file id.
+    pub fn files(&self) -> &[Range<usize>] {
+        &self.files
+    }
+
+    pub fn free(&self) -> &RangeSet {
+        &self.free
+    }
+
+    /// Moves whole files, highest id first, into the leftmost free span that
+    /// fits them.
+    pub fn compact_files(&mut self) {
+        for file in self.files.iter_mut().rev() {
+            cancel::checkpoint();
+
+            let size = file.len();
+
+            if size == 0 {
+                continue;
+            }
+
+            let Some(free_space) = self.free.first_fit(size, file.start) else {
+                continue;
+            };
+
+            let target = free_space.start..free_space.start + size;
+
+            crate::trace!("moving file from {:?} to {:?}", file, target);
+
+            self.free.remove(target.clone());
+            self.free.insert(file.clone());
+            *file = target;
+        }
+    }
+
+    pub fn checksum(&self) -> usize {
+        let mut checksum = 0;
+
+        for (id, file) in self.files.iter().enumerate() {
+            if file.is_empty() {
+                continue;
+            }
+
+            checksum += id * (file.start + file.end - 1) * file.len() / 2;
+        }
+
+        checksum
+    }
+}
+
+impl Display for SpanDiskMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let size = self
+            .files
+            .iter()
+            .chain(self.free.iter().next_back().as_ref())
+            .map(|span| span.end)
+            .max()
+            .unwrap_or(0);
+        let mut blocks = vec!['.'; size];
+
+        for (id, file) in self.files.iter().enumerate() {
+            blocks[file.clone()].fill(block_id_char(id));
+        }
+
+        for block in blocks {
+            write!(f, "{}", block)?;
+        }
+
+        Ok(())
+    }
+}
+
 pub fn part_1(input: &str) -> String {
     let mut map = DiskMap::new(input);
 
+    crate::snapshot!("disk", map);
     map.compact();
+    crate::snapshot!("compacted", map);
+
     map.checksum().to_string()
 }
 
 pub fn part_2(input: &str) -> String {
+    let mut map = SpanDiskMap::new(input);
+
+    crate::snapshot!("disk", map);
+    map.compact_files();
+    crate::snapshot!("compacted", map);
+
+    map.checksum().to_string()
+}
+
+pub fn part_2_blocks(input: &str) -> String {
     let mut map = DiskMap::new(input);
 
+    crate::snapshot!("disk", map);
     map.compact_2();
+    crate::snapshot!("compacted", map);
+
     map.checksum().to_string()
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        day09::{part_1, part_2},
+        day09::{part_1, part_2, part_2_blocks, DiskMap, SpanDiskMap},
         input, output,
     };
 
@@ -236,4 +367,26 @@ mod test {
     fn part_2_real() {
         assert_eq!(part_2(input::DAY_09), output::DAY_09_2)
     }
+
+    #[test]
+    fn part_2_blocks_real() {
+        assert_eq!(part_2_blocks(input::DAY_09), output::DAY_09_2)
+    }
+
+    #[test]
+    fn span_model_matches_blocks() {
+        let mut blocks = DiskMap::new(input::example::DAY_09);
+        let mut spans = SpanDiskMap::new(input::example::DAY_09);
+
+        assert_eq!(spans.to_string(), blocks.to_string());
+
+        blocks.compact_2();
+        spans.compact_files();
+
+        assert_eq!(
+            spans.to_string(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+        assert_eq!(spans.to_string(), blocks.to_string());
+    }
 }
@@ -0,0 +1,242 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// Most runs read at once, and so most files open at once. More runs than
+/// this are first merged into longer runs.
+const MAX_MERGE_WIDTH: usize = 64;
+
+/// Sorted values spilled to a temporary file, removed again on drop. The file
+/// is only open while the run is written and while it is read back.
+struct Run {
+    path: PathBuf,
+}
+
+impl Run {
+    fn write(values: impl IntoIterator<Item = io::Result<u32>>) -> io::Result<Self> {
+        let run = Run {
+            path: std::env::temp_dir().join(format!(
+                "aoc2024-sort-{}-{}",
+                std::process::id(),
+                NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+            )),
+        };
+
+        let mut writer = BufWriter::new(File::create(&run.path)?);
+
+        for value in values {
+            writer.write_all(&value?.to_le_bytes())?;
+        }
+
+        writer.flush()?;
+
+        Ok(run)
+    }
+
+    fn open(self) -> io::Result<RunReader> {
+        Ok(RunReader {
+            reader: BufReader::new(File::open(&self.path)?),
+            _run: self,
+        })
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct RunReader {
+    reader: BufReader<File>,
+    // Dropped after the reader, removing the file once it is closed.
+    _run: Run,
+}
+
+impl RunReader {
+    fn next(&mut self) -> io::Result<Option<u32>> {
+        let mut bytes = [0; 4];
+
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(u32::from_le_bytes(bytes))),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Sorts more values than fit in memory. Values are buffered `chunk_size` at
+/// a time, and each full buffer is sorted and written to a temporary file.
+/// At most 64 of those files are open at once.
+///
+/// ```
+/// use aoc2024::external_sort::ExternalSort;
+///
+/// let mut sort = ExternalSort::new(2);
+/// for value in [5, 3, 9, 1, 4] {
+///     sort.push(value).unwrap();
+/// }
+///
+/// let sorted: Vec<u32> = sort.finish().unwrap().map(Result::unwrap).collect();
+/// assert_eq!(sorted, vec![1, 3, 4, 5, 9]);
+/// ```
+pub struct ExternalSort {
+    chunk_size: usize,
+    chunk: Vec<u32>,
+    runs: Vec<Run>,
+}
+
+impl ExternalSort {
+    pub fn new(chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+
+        ExternalSort {
+            chunk_size,
+            chunk: Vec::with_capacity(chunk_size),
+            runs: vec![],
+        }
+    }
+
+    pub fn push(&mut self, value: u32) -> io::Result<()> {
+        self.chunk.push(value);
+
+        if self.chunk.len() == self.chunk_size {
+            self.chunk.sort_unstable();
+            self.runs
+                .push(Run::write(self.chunk.iter().copied().map(Ok))?);
+            self.chunk.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Number of runs written to disk so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Merges the spilled runs with the values still in memory.
+    pub fn finish(mut self) -> io::Result<Merge> {
+        self.chunk.sort_unstable();
+
+        // Leaves room for the values in memory in the final merge.
+        while self.runs.len() >= MAX_MERGE_WIDTH {
+            let rest = self.runs.split_off(MAX_MERGE_WIDTH);
+            let runs = std::mem::replace(&mut self.runs, rest);
+
+            self.runs.push(Run::write(Merge::new(runs, vec![])?)?);
+        }
+
+        Merge::new(self.runs, self.chunk)
+    }
+}
+
+enum Source {
+    Run(RunReader),
+    Memory(std::vec::IntoIter<u32>),
+}
+
+impl Source {
+    fn next(&mut self) -> io::Result<Option<u32>> {
+        match self {
+            Source::Run(run) => run.next(),
+            Source::Memory(values) => Ok(values.next()),
+        }
+    }
+}
+
+/// Ascending values of an [`ExternalSort`], holding one value per run in
+/// memory at a time.
+pub struct Merge {
+    heap: BinaryHeap<Reverse<(u32, usize)>>,
+    sources: Vec<Source>,
+}
+
+impl Merge {
+    fn new(runs: Vec<Run>, memory: Vec<u32>) -> io::Result<Self> {
+        let mut sources = runs
+            .into_iter()
+            .map(|run| run.open().map(Source::Run))
+            .collect::<io::Result<Vec<Source>>>()?;
+        sources.push(Source::Memory(memory.into_iter()));
+
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+
+        for (index, source) in sources.iter_mut().enumerate() {
+            if let Some(value) = source.next()? {
+                heap.push(Reverse((value, index)));
+            }
+        }
+
+        Ok(Merge { heap, sources })
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<u32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((value, index)) = self.heap.pop()?;
+
+        match self.sources[index].next() {
+            Ok(Some(next)) => self.heap.push(Reverse((next, index))),
+            Ok(None) => {}
+            Err(error) => return Some(Err(error)),
+        }
+
+        Some(Ok(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::external_sort::{ExternalSort, MAX_MERGE_WIDTH};
+
+    #[test]
+    fn external_sort_test() {
+        let values: Vec<u32> = (0..1000u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) % 97)
+            .collect();
+        let mut sort = ExternalSort::new(64);
+
+        for value in values.iter() {
+            sort.push(*value).unwrap();
+        }
+
+        assert_eq!(sort.spilled_runs(), 15);
+
+        let sorted: Vec<u32> = sort.finish().unwrap().map(Result::unwrap).collect();
+        let mut expected = values;
+        expected.sort_unstable();
+
+        assert_eq!(sorted, expected);
+        assert_eq!(ExternalSort::new(8).finish().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn merge_width_test() {
+        let values: Vec<u32> = (0..MAX_MERGE_WIDTH as u32 * 5 + 3)
+            .map(|i| i.wrapping_mul(2_654_435_761) % 1009)
+            .collect();
+        let mut sort = ExternalSort::new(1);
+
+        for value in values.iter() {
+            sort.push(*value).unwrap();
+        }
+
+        assert!(sort.spilled_runs() > MAX_MERGE_WIDTH * 4);
+
+        let sorted: Vec<u32> = sort.finish().unwrap().map(Result::unwrap).collect();
+        let mut expected = values;
+        expected.sort_unstable();
+
+        assert_eq!(sorted, expected);
+    }
+}
@@ -0,0 +1,437 @@
+use std::ops::{Index, IndexMut};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::Left => Direction::Up,
+            Direction::Right => Direction::Down,
+            Direction::Up => Direction::Right,
+            Direction::Down => Direction::Left,
+        }
+    }
+
+    pub fn heading(self) -> Heading {
+        match self {
+            Direction::Left => Heading::West,
+            Direction::Right => Heading::East,
+            Direction::Up => Heading::North,
+            Direction::Down => Heading::South,
+        }
+    }
+}
+
+/// One of the eight compass directions, with `y` growing downwards.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Heading {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 8] = [
+        Heading::North,
+        Heading::NorthEast,
+        Heading::East,
+        Heading::SouthEast,
+        Heading::South,
+        Heading::SouthWest,
+        Heading::West,
+        Heading::NorthWest,
+    ];
+
+    /// Change in `x` and `y` for one step.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Heading::North => (0, -1),
+            Heading::NorthEast => (1, -1),
+            Heading::East => (1, 0),
+            Heading::SouthEast => (1, 1),
+            Heading::South => (0, 1),
+            Heading::SouthWest => (-1, 1),
+            Heading::West => (-1, 0),
+            Heading::NorthWest => (-1, -1),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Position {
+    pub fn new(x: usize, y: usize) -> Self {
+        Position { x, y }
+    }
+
+    pub fn up(&self) -> Self {
+        Position {
+            x: self.x,
+            y: self.y - 1,
+        }
+    }
+
+    pub fn down(&self) -> Self {
+        Position {
+            x: self.x,
+            y: self.y + 1,
+        }
+    }
+
+    pub fn left(&self) -> Self {
+        Position {
+            x: self.x - 1,
+            y: self.y,
+        }
+    }
+
+    pub fn right(&self) -> Self {
+        Position {
+            x: self.x + 1,
+            y: self.y,
+        }
+    }
+
+    /// Position `steps` steps towards `heading`, or `None` if that is left of
+    /// or above the origin.
+    pub fn step(&self, heading: Heading, steps: usize) -> Option<Self> {
+        let (x, y) = heading.offset();
+
+        Some(Position {
+            x: self.x.checked_add_signed(x * steps as isize)?,
+            y: self.y.checked_add_signed(y * steps as isize)?,
+        })
+    }
+
+    pub fn dist(self, other: Position) -> (isize, isize) {
+        let x = self.x as isize - other.x as isize;
+        let y = self.y as isize - other.y as isize;
+
+        (x, y)
+    }
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Set of positions within a `width` by `height` grid, stored as one bit per
+/// cell. Cloning and clearing are a copy or fill of `width * height / 64`
+/// words.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GridSet {
+    width: usize,
+    height: usize,
+    words: Vec<u64>,
+}
+
+impl GridSet {
+    pub fn new(width: usize, height: usize) -> Self {
+        GridSet {
+            width,
+            height,
+            words: vec![0; (width * height).div_ceil(WORD_BITS)],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn bit(&self, position: &Position) -> (usize, u64) {
+        assert!(
+            position.x < self.width && position.y < self.height,
+            "position {:?} outside of {}x{} grid",
+            position,
+            self.width,
+            self.height
+        );
+
+        let index = position.y * self.width + position.x;
+        (index / WORD_BITS, 1 << (index % WORD_BITS))
+    }
+
+    /// Returns whether the position was newly inserted, like `HashSet::insert`.
+    pub fn insert(&mut self, position: Position) -> bool {
+        let (word, mask) = self.bit(&position);
+        let inserted = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        inserted
+    }
+
+    pub fn remove(&mut self, position: &Position) -> bool {
+        let (word, mask) = self.bit(position);
+        let removed = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        removed
+    }
+
+    /// Positions outside of the grid are never contained.
+    pub fn contains(&self, position: &Position) -> bool {
+        if position.x >= self.width || position.y >= self.height {
+            return false;
+        }
+
+        let (word, mask) = self.bit(position);
+        self.words[word] & mask != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Overwrites this set with `other` without reallocating.
+    pub fn copy_from(&mut self, other: &GridSet) {
+        assert!(self.width == other.width && self.height == other.height);
+        self.words.copy_from_slice(&other.words);
+    }
+
+    /// Positions in the set in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(move |(word_index, word)| {
+                let mut bits = *word;
+
+                std::iter::from_fn(move || {
+                    if bits == 0 {
+                        return None;
+                    }
+
+                    let bit = bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+
+                    let index = word_index * WORD_BITS + bit;
+                    Some(Position::new(index % self.width, index / self.width))
+                })
+            })
+    }
+}
+
+/// Value per cell of a `width` by `height` grid, stored in one flat `Vec`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GridMap<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> GridMap<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        GridMap {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Sets every cell to `value`.
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+
+    /// Overwrites this map with `other` without reallocating.
+    pub fn copy_from(&mut self, other: &GridMap<T>) {
+        assert!(self.width == other.width && self.height == other.height);
+        self.cells.clone_from_slice(&other.cells);
+    }
+}
+
+impl<T> GridMap<T> {
+    /// Builds a map from lines of equal length, converting each character.
+    pub fn parse<F>(input: &str, mut cell: F) -> Self
+    where
+        F: FnMut(char) -> T,
+    {
+        let mut width = 0;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for line in input.lines() {
+            let start = cells.len();
+            cells.extend(line.chars().map(&mut cell));
+
+            // Counted in characters, not bytes, so any UTF-8 input works.
+            if height == 0 {
+                width = cells.len();
+            } else {
+                assert!(
+                    width == cells.len() - start,
+                    "line {} has a different width",
+                    height
+                );
+            }
+
+            height += 1;
+        }
+
+        GridMap {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, position: &Position) -> Option<usize> {
+        if position.x >= self.width || position.y >= self.height {
+            return None;
+        }
+
+        Some(position.y * self.width + position.x)
+    }
+
+    pub fn get(&self, position: &Position) -> Option<&T> {
+        self.index(position).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, position: &Position) -> Option<&mut T> {
+        self.index(position).map(|index| &mut self.cells[index])
+    }
+
+    /// Cells with their positions in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (Position::new(index % self.width, index / self.width), cell))
+    }
+}
+
+impl<T> Index<Position> for GridMap<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &Self::Output {
+        self.get(&position).unwrap_or_else(|| {
+            panic!(
+                "position {:?} outside of {}x{} grid",
+                position, self.width, self.height
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<Position> for GridMap<T> {
+    fn index_mut(&mut self, position: Position) -> &mut Self::Output {
+        let (width, height) = (self.width, self.height);
+
+        self.get_mut(&position).unwrap_or_else(|| {
+            panic!(
+                "position {:?} outside of {}x{} grid",
+                position, width, height
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::grid::{GridMap, GridSet, Heading, Position};
+
+    #[test]
+    fn grid_set_test() {
+        let mut set = GridSet::new(9, 9);
+
+        assert!(set.insert(Position::new(8, 0)));
+        assert!(set.insert(Position::new(0, 8)));
+        assert!(set.insert(Position::new(1, 7)));
+        assert!(!set.insert(Position::new(8, 0)));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&Position::new(1, 7)));
+        assert!(!set.contains(&Position::new(7, 1)));
+        assert!(!set.contains(&Position::new(9, 0)));
+        assert!(!set.contains(&Position::new(0, 100)));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![
+                Position::new(8, 0),
+                Position::new(1, 7),
+                Position::new(0, 8)
+            ]
+        );
+
+        let mut copy = GridSet::new(9, 9);
+        copy.copy_from(&set);
+        assert!(copy.remove(&Position::new(8, 0)));
+        assert!(!copy.remove(&Position::new(8, 0)));
+        assert_eq!(copy.len(), 2);
+        assert_eq!(set.len(), 3);
+
+        set.clear();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn grid_map_test() {
+        let mut map = GridMap::new(3, 2, 0u8);
+
+        map[Position::new(2, 1)] = 7;
+        *map.get_mut(&Position::new(0, 1)).unwrap() += 1;
+
+        assert_eq!(map.get(&Position::new(3, 0)), None);
+        assert_eq!(map[Position::new(2, 1)], 7);
+        assert_eq!(
+            map.iter()
+                .filter(|(_, value)| **value > 0)
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>(),
+            vec![Position::new(0, 1), Position::new(2, 1)]
+        );
+
+        let map = GridMap::parse("é.\n.ü\n", |char| char);
+        assert_eq!((map.width(), map.height()), (2, 2));
+        assert_eq!(map[Position::new(1, 1)], 'ü');
+    }
+
+    #[test]
+    fn step_test() {
+        let position = Position::new(2, 1);
+
+        assert_eq!(
+            position.step(Heading::SouthWest, 2),
+            Some(Position::new(0, 3))
+        );
+        assert_eq!(
+            position.step(Heading::NorthEast, 1),
+            Some(Position::new(3, 0))
+        );
+        assert_eq!(position.step(Heading::North, 2), None);
+        assert_eq!(position.step(Heading::West, 0), Some(position));
+    }
+}
@@ -0,0 +1,28 @@
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+
+pub mod cancel;
+pub mod config;
+pub mod counter;
+pub mod cycle;
+pub mod external_sort;
+pub mod grid;
+pub mod input;
+pub mod log;
+pub mod math;
+pub mod memo;
+pub mod memory;
+pub mod output;
+pub mod progress;
+pub mod range_set;
+pub mod runner;
+pub mod search;
+pub mod solution;
+pub mod union_find;
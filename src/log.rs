@@ -0,0 +1,179 @@
+use std::{
+    fmt::{Arguments, Display},
+    io::Write,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        RwLock,
+    },
+};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn from_verbosity(verbosity: u8) -> Self {
+        match verbosity {
+            0 => Level::Warn,
+            1 => Level::Info,
+            2 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+// Highest level any target has enabled, so disabled events cost one load.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static TRACED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+fn update_max_level() {
+    let level = LEVEL.load(Ordering::Relaxed);
+    let traced = !TRACED.read().unwrap().is_empty();
+    let max = if traced { Level::Trace as u8 } else { level };
+
+    MAX_LEVEL.store(max, Ordering::Relaxed);
+}
+
+/// Sets the level for every target.
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    update_max_level();
+}
+
+/// Enables every level for `target`, such as `day06`.
+pub fn trace_target(target: &str) {
+    TRACED.write().unwrap().push(target.to_owned());
+    update_max_level();
+}
+
+/// Resets to the default of warnings and errors only, with no traced targets.
+pub fn reset() {
+    TRACED.write().unwrap().clear();
+    set_level(Level::Warn);
+}
+
+#[inline]
+pub fn max_level_enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn enabled(level: Level, target: &str) -> bool {
+    if !max_level_enabled(level) {
+        return false;
+    }
+
+    if level as u8 <= LEVEL.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    TRACED.read().unwrap().iter().any(|traced| traced == target)
+}
+
+/// Last segment of a module path, which is the day for solution modules.
+pub fn target(module_path: &str) -> &str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+pub fn write(level: Level, target: &str, args: Arguments) {
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "[{} {}] {}", level, target, args);
+}
+
+/// Writes a multi-line `Display` value under a heading.
+pub fn write_snapshot(level: Level, target: &str, label: &str, value: &dyn Display) {
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "[{} {}] {}:", level, target, label);
+    let _ = writeln!(stderr, "{}", value);
+}
+
+/// Emits an event at `level` for the calling module. The message is only
+/// formatted when the level is enabled for that module.
+#[macro_export]
+macro_rules! event {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::log::max_level_enabled($level) {
+            let target = $crate::log::target(module_path!());
+
+            if $crate::log::enabled($level, target) {
+                $crate::log::write($level, target, format_args!($($arg)+));
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { $crate::event!($crate::log::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::event!($crate::log::Level::Info, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::event!($crate::log::Level::Debug, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::event!($crate::log::Level::Trace, $($arg)+) };
+}
+
+/// Prints the `Display` of a state, such as a map, at trace level.
+#[macro_export]
+macro_rules! snapshot {
+    ($label:expr, $value:expr) => {
+        if $crate::log::max_level_enabled($crate::log::Level::Trace) {
+            let target = $crate::log::target(module_path!());
+
+            if $crate::log::enabled($crate::log::Level::Trace, target) {
+                $crate::log::write_snapshot($crate::log::Level::Trace, target, $label, &$value);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::log::{enabled, reset, set_level, target, trace_target, Level};
+
+    #[test]
+    fn filter_test() {
+        assert_eq!(target("aoc2024::day06"), "day06");
+
+        reset();
+        assert!(enabled(Level::Warn, "day06"));
+        assert!(!enabled(Level::Info, "day06"));
+
+        set_level(Level::Debug);
+        trace_target("day06");
+        assert!(enabled(Level::Debug, "day01"));
+        assert!(!enabled(Level::Trace, "day01"));
+        assert!(enabled(Level::Trace, "day06"));
+
+        reset();
+        assert!(!enabled(Level::Trace, "day06"));
+    }
+}
@@ -1,44 +1,621 @@
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-
-#[allow(dead_code)]
-mod input;
-#[allow(dead_code)]
-mod output;
+use std::{io::IsTerminal, path::PathBuf, time::Duration};
+
+use aoc2024::{
+    config::{timeout_from_secs, Config, Format},
+    day01::PairReport,
+    day02::{self, Directions, RuleOverrides, SafetyCount},
+    log::{self, Level},
+    memory::CountingAllocator,
+    progress::{self, BarRenderer, LineRenderer},
+    runner::{self, Bench, Comparison, Determinism, Outcome, Report, RunOptions, Verification},
+    solution::{self, Solution, SOLUTIONS},
+};
+use serde::Serialize;
+
+/// Only counts while `--mem` is given; otherwise it is the system allocator.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(PartialEq, Eq)]
+enum Command {
+    Run,
+    Compare,
+    Verify,
+    Bench,
+    Pairs,
+    Safety,
+}
+
+impl Command {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "run" => Some(Command::Run),
+            "compare" => Some(Command::Compare),
+            "verify" => Some(Command::Verify),
+            "bench" => Some(Command::Bench),
+            "pairs" => Some(Command::Pairs),
+            "safety" => Some(Command::Safety),
+            _ => None,
+        }
+    }
+}
+
+const USAGE: &str = "\
+usage: aoc2024 [run|compare|verify|bench|pairs|safety] [options] [days...]
+
+  --variant NAME      run this variant where a part has it
+  --config PATH       read settings from PATH instead of aoc.toml
+  --input-dir DIR     read real inputs from DIR
+  --format text|json  output format
+  --timeout SECONDS   per-part timeout
+  --threads N         rayon pool size per part
+  --mem               count allocations
+  --determinism RUNS  check answers across thread counts instead
+  --runs N            bench runs per part
+  --top N             largest terms shown by pairs
+  --part 1|2          safety preset
+  --steps 1..=3       safety step sizes
+  --direction D       increasing, decreasing, either or mixed
+  --allow-equal       allow equal neighbours
+  --removals N        levels the Problem Dampener may remove
+  --explain           diagnose every report
+  --trace DAY         trace one day, such as day06
+  -v, -vv, -vvv       more logging";
+
+/// Prints `message` with the usage and exits with status 2.
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+/// Command line flags. Settings that can also come from `aoc.toml` are
+/// `None` unless given, so that the flag overrides the file.
+struct Options {
+    command: Command,
+    days: Vec<u8>,
+    variant: Option<String>,
+    verbosity: u8,
+    traced: Vec<String>,
+    runs: usize,
+    top: usize,
+    memory: bool,
+    /// Day 2 safety rule changes for `safety`.
+    rules: RuleOverrides,
+    /// Print a day 2 diagnosis per report instead of the count.
+    explain: bool,
+    /// Replaces the command with a determinism check of this many runs.
+    determinism: Option<usize>,
+    config: Option<PathBuf>,
+    input_dir: Option<PathBuf>,
+    format: Option<Format>,
+    timeout: Option<f64>,
+    threads: Option<usize>,
+}
+
+fn parse_day(value: &str) -> Result<u8, String> {
+    let digits = value.strip_prefix("day").unwrap_or(value);
+
+    digits
+        .parse()
+        .map_err(|_| format!("expected a day number, got {}", value))
+}
+
+fn expect_value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = expect_value(flag, value)?;
+
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+/// Parses a count of at least `min`.
+fn parse_count(flag: &str, value: Option<String>, min: usize) -> Result<usize, String> {
+    let count = parse_value(flag, value)?;
+
+    if count < min {
+        return Err(format!("{} expects at least {}, got {}", flag, min, count));
+    }
+
+    Ok(count)
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        command: Command::Run,
+        days: vec![],
+        variant: None,
+        verbosity: 0,
+        traced: vec![],
+        runs: 10,
+        top: 10,
+        memory: false,
+        rules: RuleOverrides::default(),
+        explain: false,
+        determinism: None,
+        config: None,
+        input_dir: None,
+        format: None,
+        timeout: None,
+        threads: None,
+    };
+
+    let mut args = std::env::args().skip(1).peekable();
+
+    if let Some(command) = args.peek().and_then(|arg| Command::parse(arg)) {
+        options.command = command;
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--variant" => options.variant = Some(expect_value("--variant", args.next())?),
+            "--trace" => {
+                let day = parse_day(&expect_value("--trace", args.next())?)?;
+                options.traced.push(format!("day{:02}", day));
+            }
+            "--timeout" => {
+                let value = expect_value("--timeout", args.next())?;
+                let seconds = value
+                    .parse()
+                    .ok()
+                    .filter(|seconds| timeout_from_secs(*seconds).is_some())
+                    .ok_or_else(|| {
+                        format!(
+                            "--timeout expects a positive number of seconds, got {}",
+                            value
+                        )
+                    })?;
+
+                options.timeout = Some(seconds);
+            }
+            "--threads" => options.threads = Some(parse_value("--threads", args.next())?),
+            "--mem" => options.memory = true,
+            // One run has nothing to be compared with.
+            "--determinism" => {
+                options.determinism = Some(parse_count("--determinism", args.next(), 2)?)
+            }
+            "--part" => {
+                let part = parse_value("--part", args.next())?;
+
+                if part != 1 && part != 2 {
+                    return Err(format!("--part expects 1 or 2, got {}", part));
+                }
+
+                options.rules.part = Some(part);
+            }
+            "--steps" => {
+                let steps = expect_value("--steps", args.next())?;
+                options.rules.steps = Some(
+                    RuleOverrides::parse_steps(&steps)
+                        .ok_or_else(|| format!("invalid value for --steps: {}", steps))?,
+                );
+            }
+            "--direction" => {
+                let value = expect_value("--direction", args.next())?;
+                options.rules.directions = Some(
+                    Directions::parse(&value)
+                        .ok_or_else(|| format!("unknown direction {}", value))?,
+                );
+            }
+            "--allow-equal" => options.rules.allow_equal = true,
+            "--explain" => options.explain = true,
+            "--removals" => options.rules.removals = Some(parse_value("--removals", args.next())?),
+            "--top" => options.top = parse_value("--top", args.next())?,
+            "--runs" => options.runs = parse_count("--runs", args.next(), 1)?,
+            "--config" => options.config = Some(parse_value("--config", args.next())?),
+            "--input-dir" => options.input_dir = Some(parse_value("--input-dir", args.next())?),
+            "--format" => {
+                let format = expect_value("--format", args.next())?;
+                options.format = Some(
+                    Format::parse(&format).ok_or_else(|| format!("unknown format {}", format))?,
+                );
+            }
+            verbose if verbose.starts_with("-v") && verbose[1..].chars().all(|c| c == 'v') => {
+                options.verbosity += (verbose.len() - 1) as u8;
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown flag {}", flag)),
+            day => options.days.push(parse_day(day)?),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Reads `aoc.toml` and lets the command line flags override it.
+fn load_config(options: &Options) -> Result<Config, String> {
+    let config = match options.config.as_ref() {
+        Some(path) => Config::load_from(path),
+        None => Config::load(),
+    };
+
+    let mut config = config.map_err(|error| format!("invalid config: {}", error))?;
+
+    if options.input_dir.is_some() {
+        config.input_dir = options.input_dir.clone();
+    }
+    if options.format.is_some() {
+        config.format = options.format;
+    }
+    if options.timeout.is_some() {
+        config.timeout = options.timeout;
+    }
+    if options.threads.is_some() {
+        config.threads = options.threads;
+    }
+
+    Ok(config)
+}
+
+/// Prints rows as they come for text output, or as one JSON array at the end.
+struct Output {
+    format: Format,
+    rows: Vec<serde_json::Value>,
+}
+
+impl Output {
+    fn row<T: Serialize>(&mut self, row: &T, text: impl FnOnce(&T)) {
+        match self.format {
+            Format::Text => text(row),
+            Format::Json => self
+                .rows
+                .push(serde_json::to_value(row).expect("failed to serialize row")),
+        }
+    }
+
+    fn finish(self) {
+        if self.format == Format::Json {
+            let json = serde_json::to_string_pretty(&self.rows).expect("failed to serialize");
+            println!("{}", json);
+        }
+    }
+}
+
+fn part_number(one: bool) -> u8 {
+    if one {
+        1
+    } else {
+        2
+    }
+}
 
 fn print_result(result: &str, day: u8, one: bool) {
+    println!("Day={}, Part={}, Result={}", day, part_number(one), result);
+}
+
+fn print_report(report: &Report, outcome: &Outcome) {
+    match report.memory {
+        Some(memory) => println!(
+            "Day={}, Part={}, Result={}, {}",
+            report.day, report.part, outcome, memory
+        ),
+        None => print_result(&outcome.to_string(), report.day, report.part == 1),
+    }
+}
+
+fn format_millis(millis: Option<f64>) -> String {
+    millis
+        .map(|millis| format!("{:.2?}", Duration::from_secs_f64(millis / 1000.0)))
+        .unwrap_or_default()
+}
+
+fn print_comparison(comparison: &Comparison) {
     println!(
-        "Day={}, Part={}, Result={}",
-        day,
-        if one { 1 } else { 2 },
-        result
+        "Day={}, Part={}, Input={}, Agree={}",
+        comparison.day, comparison.part, comparison.input, comparison.agree
     );
+
+    for report in comparison.results.iter() {
+        let result = report.answer.as_ref().or(report.error.as_ref());
+
+        println!(
+            "  {:<10} {:<20} {:>10}",
+            report.variant,
+            result.map(String::as_str).unwrap_or_default(),
+            format_millis(report.elapsed_ms)
+        );
+    }
+}
+
+fn print_verification(verification: &Verification) {
+    let report = &verification.report;
+    let result = report.answer.as_ref().or(report.error.as_ref());
+
+    println!(
+        "Day={}, Part={}, Input={}, Result={}, Expected={}, Correct={}",
+        report.day,
+        report.part,
+        report.input,
+        result.map(String::as_str).unwrap_or_default(),
+        verification.expected.unwrap_or("unknown"),
+        verification
+            .correct
+            .map_or("unknown".to_owned(), |correct| correct.to_string())
+    );
+}
+
+fn print_bench(bench: &Bench) {
+    println!(
+        "Day={}, Part={}, Variant={}, Runs={}, Min={}, Mean={}, Max={}",
+        bench.day,
+        bench.part,
+        bench.variant,
+        bench.runs,
+        format_millis(Some(bench.min_ms)),
+        format_millis(Some(bench.mean_ms)),
+        format_millis(Some(bench.max_ms))
+    );
+}
+
+fn print_determinism(determinism: &Determinism) {
+    println!(
+        "Day={}, Part={}, Variant={}, Runs={}, Deterministic={}",
+        determinism.day,
+        determinism.part,
+        determinism.variant,
+        determinism.runs.len(),
+        determinism.deterministic
+    );
+
+    for run in determinism.runs.iter() {
+        let report = &run.report;
+        let result = report.answer.as_ref().or(report.error.as_ref());
+
+        println!(
+            "  threads={:<4} {:<20} {:>10}",
+            run.threads,
+            result.map(String::as_str).unwrap_or_default(),
+            format_millis(report.elapsed_ms)
+        );
+    }
+}
+
+struct Runner {
+    options: Options,
+    config: Config,
+    run_options: RunOptions,
+    output: Output,
+    success: bool,
+}
+
+impl Runner {
+    fn input(&self, solution: &Solution) -> String {
+        self.config.input(solution).unwrap_or_else(|error| {
+            eprintln!(
+                "error: failed to read input for day {}: {}",
+                solution.day, error
+            );
+            std::process::exit(1);
+        })
+    }
+
+    /// Variant chosen with `--variant`, or the default one with a warning when
+    /// this part has no variant of that name.
+    fn variant(&self, solution: &Solution, one: bool) -> &'static solution::Variant {
+        let default = &solution.variants(one)[0];
+
+        let Some(name) = self.options.variant.as_ref() else {
+            return default;
+        };
+
+        solution.variant(one, name).unwrap_or_else(|| {
+            aoc2024::warn!(
+                "day {} part {} has no variant {}, using {}",
+                solution.day,
+                if one { 1 } else { 2 },
+                name,
+                default.name
+            );
+            default
+        })
+    }
+
+    fn run(&mut self, solution: &Solution) {
+        let input = self.input(solution);
+
+        for one in [true, false] {
+            let variant = self.variant(solution, one);
+            let outcome = runner::run_part(variant.solver, &input, &self.run_options);
+            let report = Report::new(solution.day, one, variant.name, "real", &outcome);
+
+            self.output
+                .row(&report, |report| print_report(report, &outcome));
+        }
+    }
+
+    fn compare(&mut self, solution: &Solution) {
+        let input = self.input(solution);
+        let inputs = [("example", solution.example), ("real", input.as_str())];
+
+        for one in [true, false] {
+            if solution.variants(one).len() < 2 && self.options.days.is_empty() {
+                continue;
+            }
+
+            for comparison in runner::compare(solution, one, &inputs, &self.run_options) {
+                self.success &= comparison.agree;
+                self.output.row(&comparison, print_comparison);
+            }
+        }
+    }
+
+    /// Checks the example and real answers. The recorded real answer is for
+    /// the built-in input, so it is unknown for inputs from `input_dir`.
+    fn verify(&mut self, solution: &Solution) {
+        let input = self.input(solution);
+        let builtin = self.config.input_dir.is_none();
+
+        for one in [true, false] {
+            let variant = self.variant(solution, one);
+            let inputs = [
+                (
+                    "example",
+                    solution.example,
+                    Some(solution.example_answer(one)),
+                ),
+                (
+                    "real",
+                    input.as_str(),
+                    builtin.then(|| solution.answer(one)),
+                ),
+            ];
+
+            for verification in runner::verify(solution, one, variant, &inputs, &self.run_options) {
+                self.success &= verification.correct != Some(false);
+                self.output.row(&verification, print_verification);
+            }
+        }
+    }
+
+    fn determinism(&mut self, solution: &Solution, runs: usize) {
+        let input = self.input(solution);
+
+        for one in [true, false] {
+            let variant = self.variant(solution, one);
+            let check =
+                runner::determinism(solution, one, variant, &input, runs, &self.run_options);
+
+            self.success &= check.deterministic;
+            self.output.row(&check, print_determinism);
+        }
+    }
+
+    /// Breaks the day 1 answers down into their terms.
+    fn pairs(&mut self, solution: &Solution) {
+        if solution.day != 1 {
+            return;
+        }
+
+        let report = PairReport::new(&self.input(solution), self.options.top);
+        self.output.row(&report, |report| print!("{}", report));
+    }
+
+    /// Counts safe day 2 reports under the preset rules with any overrides.
+    fn safety(&mut self, solution: &Solution) {
+        if solution.day != 2 {
+            return;
+        }
+
+        let input = self.input(solution);
+        let rules = self.options.rules.rules();
+
+        if self.options.explain {
+            for diagnosis in day02::diagnose(&input, &rules) {
+                self.output
+                    .row(&diagnosis, |diagnosis| println!("{}", diagnosis));
+            }
+
+            return;
+        }
+
+        let count = SafetyCount::new(&input, rules);
+        self.output.row(&count, |count| println!("{}", count));
+    }
+
+    fn bench(&mut self, solution: &Solution) {
+        let input = self.input(solution);
+
+        for one in [true, false] {
+            let variant = self.variant(solution, one);
+
+            match runner::bench(variant.solver, &input, self.options.runs, &self.run_options) {
+                Ok(samples) => {
+                    let bench = Bench::new(solution.day, one, variant.name, &samples);
+                    self.output.row(&bench, print_bench);
+                }
+                Err(outcome) => {
+                    self.success = false;
+                    let report = Report::new(solution.day, one, variant.name, "real", &outcome);
+
+                    self.output.row(&report, |_| {
+                        print_result(&outcome.to_string(), solution.day, one)
+                    });
+                }
+            }
+        }
+    }
 }
 
 fn main() {
-    print_result(&day01::part_1(input::DAY_01), 1, true);
-    print_result(&day01::part_2(input::DAY_01), 1, false);
-    print_result(&day02::part_1(input::DAY_02), 2, true);
-    print_result(&day02::part_2(input::DAY_02), 2, false);
-    print_result(&day03::part_1(input::DAY_03), 3, true);
-    print_result(&day03::part_2(input::DAY_03), 3, false);
-    print_result(&day04::part_1(input::DAY_04), 4, true);
-    print_result(&day04::part_2(input::DAY_04), 4, false);
-    print_result(&day05::part_1(input::DAY_05), 5, true);
-    print_result(&day05::part_2(input::DAY_05), 5, false);
-    print_result(&day06::part_1(input::DAY_06), 6, true);
-    print_result(&day06::part_2(input::DAY_06), 6, false);
-    print_result(&day07::part_1(input::DAY_07), 7, true);
-    print_result(&day07::part_2(input::DAY_07), 7, false);
-    print_result(&day08::part_1(input::DAY_08), 8, true);
-    print_result(&day08::part_2(input::DAY_08), 8, false);
-    print_result(&day09::part_1(input::DAY_09), 9, true);
-    print_result(&day09::part_2(input::DAY_09), 9, false);
+    let options = parse_args().unwrap_or_else(|message| usage_error(&message));
+    let config = load_config(&options).unwrap_or_else(|message| usage_error(&message));
+
+    log::set_level(Level::from_verbosity(options.verbosity));
+
+    for target in options.traced.iter() {
+        log::trace_target(target);
+    }
+
+    if std::io::stderr().is_terminal() {
+        progress::set_sink(Box::new(BarRenderer::new(40)));
+    } else {
+        progress::set_sink(Box::new(LineRenderer::new(Duration::from_secs(1))));
+    }
+
+    for day in options.days.iter() {
+        if solution::find(*day).is_none() {
+            usage_error(&format!("no solution registered for day {}", day));
+        }
+    }
+
+    if let Some(name) = options.variant.as_ref() {
+        let known = SOLUTIONS.iter().any(|solution| {
+            solution.variant(true, name).is_some() || solution.variant(false, name).is_some()
+        });
+
+        if !known {
+            usage_error(&format!("no solution has a variant named {}", name));
+        }
+    }
+
+    let mut runner = Runner {
+        run_options: RunOptions {
+            timeout: config.timeout(),
+            threads: config.threads,
+            memory: options.memory,
+        },
+        output: Output {
+            format: config.format.unwrap_or_default(),
+            rows: vec![],
+        },
+        options,
+        config,
+        success: true,
+    };
+
+    for solution in SOLUTIONS {
+        let selected = if runner.options.days.is_empty() {
+            !runner.config.skip.contains(&solution.day)
+        } else {
+            runner.options.days.contains(&solution.day)
+        };
+
+        if !selected {
+            continue;
+        }
+
+        if let Some(runs) = runner.options.determinism {
+            runner.determinism(solution, runs);
+            continue;
+        }
+
+        match runner.options.command {
+            Command::Run => runner.run(solution),
+            Command::Compare => runner.compare(solution),
+            Command::Verify => runner.verify(solution),
+            Command::Bench => runner.bench(solution),
+            Command::Pairs => runner.pairs(solution),
+            Command::Safety => runner.safety(solution),
+        }
+    }
+
+    let success = runner.success;
+    runner.output.finish();
+
+    if !success {
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,236 @@
+/// Number of decimal digits in `value`, counting `0` as one digit.
+pub fn digit_count(value: u64) -> u32 {
+    value.checked_ilog10().unwrap_or(0) + 1
+}
+
+/// `10^exponent`, or `None` if it does not fit in a `u64`.
+pub fn pow10(exponent: u32) -> Option<u64> {
+    10u64.checked_pow(exponent)
+}
+
+/// Decimal digits of `value`, most significant first.
+pub fn digits(value: u64) -> Vec<u8> {
+    let mut digits = Vec::with_capacity(digit_count(value) as usize);
+    let mut rest = value;
+
+    loop {
+        digits.push((rest % 10) as u8);
+        rest /= 10;
+
+        if rest == 0 {
+            break;
+        }
+    }
+
+    digits.reverse();
+    digits
+}
+
+/// Splits off the `low` least significant digits: `split_digits(123456, 2)` is
+/// `(1234, 56)`.
+pub fn split_digits(value: u64, low: u32) -> (u64, u64) {
+    match pow10(low) {
+        Some(divisor) => (value / divisor, value % divisor),
+        None => (0, value),
+    }
+}
+
+/// Digits of `lhs` followed by the digits of `rhs`, or `None` on overflow.
+pub fn checked_concat(lhs: u64, rhs: u64) -> Option<u64> {
+    lhs.checked_mul(pow10(digit_count(rhs))?)?.checked_add(rhs)
+}
+
+pub fn gcd(a: u64, b: u64) -> u64 {
+    let (mut a, mut b) = (a, b);
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Least common multiple, or `None` on overflow. `lcm(0, n)` is `0`.
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Returns `(g, x, y)` with `g = gcd(a, b) >= 0` and `a * x + b * y = g`.
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = extended_gcd_wide(a as i128, b as i128);
+    (g as i64, x as i64, y as i64)
+}
+
+fn extended_gcd_wide(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+        (old_t, t) = (t, old_t - quotient * t);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+/// `x` in `0..modulus` with `a * x ≡ 1 (mod modulus)`, if `a` is invertible.
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    if modulus <= 0 {
+        return None;
+    }
+
+    let modulus = modulus as i128;
+    let (g, x, _) = extended_gcd_wide((a as i128).rem_euclid(modulus), modulus);
+
+    if g != 1 {
+        return None;
+    }
+
+    Some(x.rem_euclid(modulus) as i64)
+}
+
+/// Solves `x ≡ residue (mod modulus)` for every `(residue, modulus)` pair.
+///
+/// Returns `(x, m)` where `m` is the lcm of the moduli and `x` is the unique
+/// solution in `0..m`. Moduli need not be coprime; `None` means the system is
+/// inconsistent or `m` does not fit in an `i64`.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut x: i128 = 0;
+    let mut m: i128 = 1;
+
+    for &(residue, modulus) in congruences {
+        if modulus <= 0 {
+            return None;
+        }
+
+        let modulus = modulus as i128;
+        let residue = (residue as i128).rem_euclid(modulus);
+        let (g, p, _) = extended_gcd_wide(m, modulus);
+
+        if (residue - x) % g != 0 {
+            return None;
+        }
+
+        let step = modulus / g;
+        let k = ((residue - x) / g).rem_euclid(step) * p.rem_euclid(step) % step;
+
+        x += m * k;
+        m *= step;
+
+        if m > i64::MAX as i128 {
+            return None;
+        }
+
+        x = x.rem_euclid(m);
+    }
+
+    Some((x as i64, m as i64))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{
+        checked_concat, crt, digit_count, digits, extended_gcd, gcd, lcm, mod_inverse, pow10,
+        split_digits,
+    };
+
+    #[test]
+    fn digit_count_test() {
+        assert_eq!(digit_count(0), 1);
+        assert_eq!(digit_count(9), 1);
+        assert_eq!(digit_count(10), 2);
+        assert_eq!(digit_count(999_999_999_999_999_999), 18);
+        assert_eq!(digit_count(1_000_000_000_000_000_000), 19);
+        assert_eq!(digit_count(u64::MAX), 20);
+    }
+
+    #[test]
+    fn digits_test() {
+        assert_eq!(pow10(19), Some(10_000_000_000_000_000_000));
+        assert_eq!(pow10(20), None);
+        assert_eq!(digits(0), vec![0]);
+        assert_eq!(digits(1024), vec![1, 0, 2, 4]);
+        assert_eq!(split_digits(123456, 2), (1234, 56));
+        assert_eq!(split_digits(123456, 0), (123456, 0));
+        assert_eq!(split_digits(u64::MAX, 20), (0, u64::MAX));
+        assert_eq!(split_digits(u64::MAX, 25), (0, u64::MAX));
+    }
+
+    #[test]
+    fn concat_test() {
+        assert_eq!(checked_concat(12, 345), Some(12345));
+        assert_eq!(checked_concat(12, 0), Some(120));
+        assert_eq!(checked_concat(0, 7), Some(7));
+        assert_eq!(
+            checked_concat(1, 999_999_999_999_999_999),
+            Some(1_999_999_999_999_999_999)
+        );
+        assert_eq!(
+            checked_concat(1, 8_446_744_073_709_551_615),
+            Some(18_446_744_073_709_551_615)
+        );
+        assert_eq!(checked_concat(1, 8_446_744_073_709_551_616), None);
+        assert_eq!(checked_concat(2, 1_000_000_000_000_000_000), None);
+        assert_eq!(checked_concat(u64::MAX, 0), None);
+    }
+
+    #[test]
+    fn gcd_lcm_test() {
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(u64::MAX, u64::MAX - 1), 1);
+        assert_eq!(lcm(0, 5), Some(0));
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(lcm(u64::MAX, 2), None);
+    }
+
+    #[test]
+    fn extended_gcd_test() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(0, 0), (0, 1, 0));
+        assert_eq!(extended_gcd(-4, 6).0, 2);
+
+        let (g, x, y) = extended_gcd(i64::MAX, i64::MAX - 1);
+        assert_eq!(g, 1);
+        assert_eq!(
+            i64::MAX as i128 * x as i128 + (i64::MAX - 1) as i128 * y as i128,
+            1
+        );
+    }
+
+    #[test]
+    fn mod_inverse_test() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(4, 8), None);
+        assert_eq!(mod_inverse(5, 1), Some(0));
+        assert_eq!(mod_inverse(5, 0), None);
+        assert_eq!(mod_inverse(2, i64::MAX), Some(i64::MAX / 2 + 1));
+    }
+
+    #[test]
+    fn crt_test() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(-1, 4), (3, 6)]), Some((3, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[(0, i64::MAX), (1, 2)]), None);
+        assert_eq!(
+            crt(&[(1, 3_037_000_499), (0, 3_037_000_493)]),
+            Some((7_686_143_343_414_038_257, 9_223_372_012_704_246_007))
+        );
+    }
+}
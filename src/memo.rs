@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        RwLock,
+    },
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub entries: usize,
+}
+
+impl MemoStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+
+        if lookups == 0 {
+            return 0.0;
+        }
+
+        self.hits as f64 / lookups as f64
+    }
+}
+
+impl Display for MemoStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hits={}, misses={}, entries={}, hit_rate={:.1}%",
+            self.hits,
+            self.misses,
+            self.entries,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+/// Cache of function results keyed on the function arguments.
+///
+/// The cache is shared by reference, so a recursive solver passes `&Memo`
+/// down to itself and rayon workers can share one instance. The lock is not
+/// held while a value is computed, which lets the computation recurse into the
+/// same cache. Two threads missing on the same key may both compute it.
+///
+/// ```
+/// use aoc2024::memo::Memo;
+///
+/// fn fib(memo: &Memo<u64, u64>, n: u64) -> u64 {
+///     if n < 2 {
+///         return n;
+///     }
+///     memo.get_or_insert_with(n, || fib(memo, n - 1) + fib(memo, n - 2))
+/// }
+///
+/// let memo = Memo::new();
+/// assert_eq!(fib(&memo, 90), 2880067194370816120);
+/// ```
+pub struct Memo<K, V> {
+    cache: RwLock<HashMap<K, V>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<K: Hash + Eq, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo {
+            cache: RwLock::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let value = self.cache.read().unwrap().get(key).cloned();
+
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        value
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.cache.write().unwrap().insert(key, value);
+    }
+
+    pub fn get_or_insert_with<F>(&self, key: K, compute: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+
+        let value = compute();
+        self.insert(key, value.clone());
+
+        value
+    }
+
+    pub fn clear(&self) {
+        self.cache.write().unwrap().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        MemoStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.cache.read().unwrap().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+
+    use crate::memo::{Memo, MemoStats};
+
+    fn paths(memo: &Memo<(usize, usize), u64>, x: usize, y: usize) -> u64 {
+        if x == 0 || y == 0 {
+            return 1;
+        }
+
+        memo.get_or_insert_with((x, y), || paths(memo, x - 1, y) + paths(memo, x, y - 1))
+    }
+
+    #[test]
+    fn recursive_test() {
+        let memo = Memo::new();
+
+        assert_eq!(paths(&memo, 16, 16), 601080390);
+        assert_eq!(
+            memo.stats(),
+            MemoStats {
+                hits: 15 * 15,
+                misses: 16 * 16,
+                entries: 16 * 16,
+            }
+        );
+    }
+
+    #[test]
+    fn parallel_test() {
+        let memo = Memo::new();
+
+        let sum: u64 = (0..32usize)
+            .into_par_iter()
+            .map(|n| paths(&memo, n, 8))
+            .sum();
+
+        assert_eq!(sum, (0..32).map(|n| paths(&Memo::new(), n, 8)).sum());
+        assert_eq!(memo.stats().entries, 31 * 8);
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Display,
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering},
+};
+
+#[cfg(test)]
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+// Live bytes since the last reset. Frees of memory allocated before the reset
+// can take this below zero.
+static LIVE: AtomicIsize = AtomicIsize::new(0);
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+/// Held by tests that turn counting on or off, since the counters are shared
+/// by every test thread.
+#[cfg(test)]
+pub(crate) static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Wraps the system allocator and counts every allocation while counting is
+/// enabled. Only a binary can install it:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+///
+/// Counts are process wide, so they only describe one part when nothing else
+/// runs alongside it.
+pub struct CountingAllocator;
+
+fn record_alloc(size: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(size, Ordering::Relaxed);
+
+    let live = LIVE.fetch_add(size as isize, Ordering::Relaxed) + size as isize;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+fn record_dealloc(size: usize) {
+    if ENABLED.load(Ordering::Relaxed) {
+        LIVE.fetch_sub(size as isize, Ordering::Relaxed);
+    }
+}
+
+fn record_realloc(old_size: usize, new_size: usize) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    BYTES.fetch_add(new_size.saturating_sub(old_size), Ordering::Relaxed);
+
+    let change = new_size as isize - old_size as isize;
+    let live = LIVE.fetch_add(change, Ordering::Relaxed) + change;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_alloc(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record_dealloc(layout.size());
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    /// Not counted as an allocation; only growth adds to the allocated bytes.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_realloc(layout.size(), new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Turns counting on or off. Counting costs a few atomic adds per allocation.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Zeroes the counters, making the current live memory the baseline for peak.
+pub fn reset() {
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    BYTES.store(0, Ordering::Relaxed);
+    LIVE.store(0, Ordering::Relaxed);
+    PEAK.store(0, Ordering::Relaxed);
+}
+
+/// Allocation counts since the last reset.
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct MemoryStats {
+    pub allocations: usize,
+    pub bytes: usize,
+    /// Most memory live at once, above what was live at the reset.
+    pub peak_bytes: usize,
+}
+
+pub fn stats() -> MemoryStats {
+    MemoryStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+        peak_bytes: PEAK.load(Ordering::Relaxed).max(0) as usize,
+    }
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Allocations={}, Bytes={}, Peak={}",
+            self.allocations, self.bytes, self.peak_bytes
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::{reset, set_enabled, stats, CountingAllocator, TEST_LOCK};
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn counting_test() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+
+        set_enabled(true);
+        reset();
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(4096);
+        buffer.extend(std::iter::repeat_n(1, 4096));
+        drop(buffer);
+        let boxed = Box::new([0u8; 1024]);
+
+        let after = stats();
+        set_enabled(false);
+        drop(boxed);
+
+        // Other tests allocate at the same time, so only lower bounds hold.
+        assert!(after.allocations >= 2);
+        assert!(after.bytes >= 4096 + 1024);
+        assert!(after.peak_bytes >= 4096);
+    }
+
+    #[test]
+    fn realloc_test() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(1024);
+        set_enabled(true);
+        reset();
+
+        buffer.reserve_exact(4096);
+        let after = stats();
+        set_enabled(false);
+        drop(buffer);
+
+        // Only the growth is new memory. Other tests allocate at the same
+        // time, so this is a lower bound too.
+        assert!(after.bytes >= 4096 - 1024);
+        assert!(after.peak_bytes >= 4096 - 1024);
+    }
+}
@@ -1,4 +1,3 @@
-#[allow(dead_code)]
 pub mod example {
     pub const DAY_01_1: &str = "11";
     pub const DAY_01_2: &str = "31";
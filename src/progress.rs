@@ -0,0 +1,191 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Receives progress updates. Implementations are called from whichever
+/// thread made progress, so they must do their own synchronisation.
+pub trait ProgressSink: Send + Sync {
+    fn update(&self, label: &str, done: usize, total: usize);
+    fn finish(&self, label: &str, total: usize);
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SINK: RwLock<Option<Box<dyn ProgressSink>>> = RwLock::new(None);
+
+/// Routes progress of every solution to `sink`, replacing any previous one.
+pub fn set_sink(sink: Box<dyn ProgressSink>) {
+    *SINK.write().unwrap() = Some(sink);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn clear_sink() {
+    ENABLED.store(false, Ordering::Relaxed);
+    *SINK.write().unwrap() = None;
+}
+
+fn report(label: &str, done: usize, total: usize) {
+    if let Some(sink) = SINK.read().unwrap().as_ref() {
+        if done >= total {
+            sink.finish(label, total);
+        } else {
+            sink.update(label, done, total);
+        }
+    }
+}
+
+/// Counter for "n of total" progress that can be shared with rayon workers.
+///
+/// The sink hears about each whole percent at most once, and nothing at all
+/// is reported when no sink is installed.
+pub struct Progress {
+    label: &'static str,
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize) -> Self {
+        Progress {
+            label,
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    pub fn inc(&self, amount: usize) {
+        let before = self.done.fetch_add(amount, Ordering::Relaxed);
+
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let after = before + amount;
+
+        if after >= self.total || before * 100 / self.total != after * 100 / self.total {
+            report(self.label, after.min(self.total), self.total);
+        }
+    }
+
+    pub fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+}
+
+/// Redraws a single progress bar line on stderr, for terminals.
+pub struct BarRenderer {
+    width: usize,
+}
+
+impl BarRenderer {
+    pub fn new(width: usize) -> Self {
+        BarRenderer { width }
+    }
+}
+
+impl ProgressSink for BarRenderer {
+    fn update(&self, label: &str, done: usize, total: usize) {
+        let filled = self.width * done / total.max(1);
+        let mut stderr = std::io::stderr().lock();
+
+        let _ = write!(
+            stderr,
+            "\r\x1b[2K{} [{}{}] {}/{} ({}%)",
+            label,
+            "#".repeat(filled),
+            "-".repeat(self.width - filled),
+            done,
+            total,
+            done * 100 / total.max(1)
+        );
+        let _ = stderr.flush();
+    }
+
+    fn finish(&self, _label: &str, _total: usize) {
+        let mut stderr = std::io::stderr().lock();
+
+        let _ = write!(stderr, "\r\x1b[2K");
+        let _ = stderr.flush();
+    }
+}
+
+/// Writes at most one progress line per `interval` on stderr, for logs.
+pub struct LineRenderer {
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl LineRenderer {
+    pub fn new(interval: Duration) -> Self {
+        LineRenderer {
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+}
+
+impl ProgressSink for LineRenderer {
+    fn update(&self, label: &str, done: usize, total: usize) {
+        let mut last = self.last.lock().unwrap();
+
+        if last.is_some_and(|last| last.elapsed() < self.interval) {
+            return;
+        }
+
+        *last = Some(Instant::now());
+        eprintln!(
+            "progress {}: {}/{} ({}%)",
+            label,
+            done,
+            total,
+            done * 100 / total.max(1)
+        );
+    }
+
+    fn finish(&self, label: &str, total: usize) {
+        *self.last.lock().unwrap() = None;
+        eprintln!("progress {}: {}/{} (100%)", label, total, total);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use rayon::prelude::*;
+
+    use crate::progress::{clear_sink, set_sink, Progress, ProgressSink};
+
+    struct Recorder(Arc<Mutex<Vec<(usize, usize)>>>);
+
+    impl ProgressSink for Recorder {
+        fn update(&self, label: &str, done: usize, total: usize) {
+            if label == "progress_test" {
+                self.0.lock().unwrap().push((done, total));
+            }
+        }
+
+        fn finish(&self, label: &str, total: usize) {
+            self.update(label, total, total);
+        }
+    }
+
+    #[test]
+    fn progress_test() {
+        let updates = Arc::new(Mutex::new(vec![]));
+        set_sink(Box::new(Recorder(updates.clone())));
+
+        let progress = Progress::new("progress_test", 1000);
+        (0..1000).into_par_iter().for_each(|_| progress.inc(1));
+        clear_sink();
+
+        let updates = updates.lock().unwrap();
+        assert_eq!(progress.done(), 1000);
+        assert_eq!(updates.len(), 100);
+        assert!(updates.contains(&(1000, 1000)));
+    }
+}
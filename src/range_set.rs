@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Add, Bound, Range, Sub},
+};
+
+/// Set of integers stored as disjoint, non-adjacent half-open ranges keyed by
+/// their start.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RangeSet<T = usize> {
+    ranges: BTreeMap<T, T>,
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> Self {
+        RangeSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> RangeSet<T>
+where
+    T: Copy + Ord + Default + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `range`, merging it with any overlapping or adjacent ranges.
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut start = range.start;
+        let mut end = range.end;
+
+        if let Some((&before_start, &before_end)) = self.ranges.range(..=start).next_back() {
+            if before_end >= start {
+                start = before_start;
+                end = end.max(before_end);
+                self.ranges.remove(&before_start);
+            }
+        }
+
+        while let Some((&next_start, &next_end)) = self.ranges.range(start..=end).next() {
+            end = end.max(next_end);
+            self.ranges.remove(&next_start);
+        }
+
+        self.ranges.insert(start, end);
+    }
+
+    /// Removes `range`, splitting ranges that extend past either side of it.
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if let Some((&before_start, &before_end)) = self.ranges.range(..range.start).next_back() {
+            if before_end > range.start {
+                self.ranges.insert(before_start, range.start);
+
+                if before_end > range.end {
+                    self.ranges.insert(range.end, before_end);
+                }
+            }
+        }
+
+        while let Some((&next_start, &next_end)) = self.ranges.range(range.clone()).next() {
+            self.ranges.remove(&next_start);
+
+            if next_end > range.end {
+                self.ranges.insert(range.end, next_end);
+            }
+        }
+    }
+
+    /// Adds every range of `other`.
+    pub fn merge(&mut self, other: &RangeSet<T>) {
+        for range in other.iter() {
+            self.insert(range);
+        }
+    }
+
+    /// Moves everything at or after `at` into a new set, splitting the range
+    /// that contains `at`.
+    pub fn split_off(&mut self, at: T) -> RangeSet<T> {
+        let mut after = RangeSet {
+            ranges: self.ranges.split_off(&at),
+        };
+
+        if let Some((_, before_end)) = self.ranges.iter_mut().next_back() {
+            if *before_end > at {
+                after.ranges.insert(at, *before_end);
+                *before_end = at;
+            }
+        }
+
+        after
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.range_containing(value).is_some()
+    }
+
+    pub fn range_containing(&self, value: T) -> Option<Range<T>> {
+        let (&start, &end) = self.ranges.range(..=value).next_back()?;
+
+        if value < end {
+            return Some(start..end);
+        }
+
+        None
+    }
+
+    /// Number of disjoint ranges.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers covered.
+    pub fn covered(&self) -> T {
+        self.iter()
+            .fold(T::default(), |sum, range| sum + (range.end - range.start))
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Range<T>> + '_ {
+        self.ranges.iter().map(|(&start, &end)| start..end)
+    }
+
+    /// First range of at least `size` that starts before `limit`, with only
+    /// the part before `limit` counting towards its size.
+    pub fn first_fit(&self, size: T, limit: T) -> Option<Range<T>> {
+        self.ranges
+            .range(..limit)
+            .map(|(&start, &end)| start..end.min(limit))
+            .find(|range| range.end - range.start >= size)
+    }
+
+    /// First uncovered span of at least `size` within `bounds`.
+    pub fn first_gap(&self, size: T, bounds: Range<T>) -> Option<Range<T>> {
+        let mut cursor = bounds.start;
+
+        if let Some(range) = self.range_containing(cursor) {
+            cursor = range.end;
+        }
+
+        let after = self
+            .ranges
+            .range((Bound::Excluded(bounds.start), Bound::Unbounded));
+
+        for (&start, &end) in after {
+            if cursor >= bounds.end {
+                return None;
+            }
+
+            let gap_end = start.min(bounds.end);
+
+            if gap_end > cursor && gap_end - cursor >= size {
+                return Some(cursor..gap_end);
+            }
+
+            cursor = cursor.max(end);
+        }
+
+        if cursor < bounds.end && bounds.end - cursor >= size {
+            return Some(cursor..bounds.end);
+        }
+
+        None
+    }
+}
+
+impl<T> FromIterator<Range<T>> for RangeSet<T>
+where
+    T: Copy + Ord + Default + Add<Output = T> + Sub<Output = T>,
+{
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = RangeSet::new();
+
+        for range in iter {
+            set.insert(range);
+        }
+
+        set
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::range_set::RangeSet;
+
+    fn ranges(set: &RangeSet) -> Vec<(usize, usize)> {
+        set.iter().map(|range| (range.start, range.end)).collect()
+    }
+
+    #[test]
+    fn insert_test() {
+        let mut set: RangeSet = [0..2, 5..7, 10..12].into_iter().collect();
+        assert_eq!(ranges(&set), vec![(0, 2), (5, 7), (10, 12)]);
+
+        set.insert(2..3);
+        set.insert(4..4);
+        assert_eq!(ranges(&set), vec![(0, 3), (5, 7), (10, 12)]);
+
+        set.insert(6..11);
+        assert_eq!(ranges(&set), vec![(0, 3), (5, 12)]);
+
+        set.insert(1..20);
+        assert_eq!(ranges(&set), vec![(0, 20)]);
+        assert_eq!(set.covered(), 20);
+    }
+
+    #[test]
+    fn remove_test() {
+        let mut set: RangeSet = [0..10, 12..15, 20..30].into_iter().collect();
+
+        set.remove(3..5);
+        assert_eq!(ranges(&set), vec![(0, 3), (5, 10), (12, 15), (20, 30)]);
+
+        set.remove(8..25);
+        assert_eq!(ranges(&set), vec![(0, 3), (5, 8), (25, 30)]);
+
+        set.remove(0..3);
+        assert!(!set.contains(0));
+        assert!(set.contains(5));
+        assert!(!set.contains(8));
+        assert_eq!(set.range_containing(27), Some(25..30));
+    }
+
+    #[test]
+    fn split_off_test() {
+        let mut set: RangeSet = [0..4, 6..10].into_iter().collect();
+        let after = set.split_off(8);
+
+        assert_eq!(ranges(&set), vec![(0, 4), (6, 8)]);
+        assert_eq!(ranges(&after), vec![(8, 10)]);
+
+        set.merge(&after);
+        assert_eq!(ranges(&set), vec![(0, 4), (6, 10)]);
+    }
+
+    #[test]
+    fn fit_and_gap_test() {
+        let set: RangeSet = [2..3, 5..9, 10..11].into_iter().collect();
+
+        assert_eq!(set.first_fit(2, 100), Some(5..9));
+        assert_eq!(set.first_fit(3, 7), None);
+        assert_eq!(set.first_fit(2, 7), Some(5..7));
+
+        assert_eq!(set.first_gap(1, 0..20), Some(0..2));
+        assert_eq!(set.first_gap(2, 0..20), Some(0..2));
+        assert_eq!(set.first_gap(2, 2..20), Some(3..5));
+        assert_eq!(set.first_gap(3, 0..20), Some(11..20));
+        assert_eq!(set.first_gap(3, 0..13), None);
+        assert_eq!(set.first_gap(1, 6..10), Some(9..10));
+    }
+}
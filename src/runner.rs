@@ -0,0 +1,580 @@
+use std::{
+    any::Any,
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rayon::ThreadPoolBuilder;
+use serde::Serialize;
+
+use crate::{
+    cancel::{self, CancelToken, Cancelled},
+    memory::{self, MemoryStats},
+    solution::{Solution, Solver, Variant},
+};
+
+/// How long a cancelled part gets to reach a checkpoint and unwind.
+const CANCEL_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunOptions {
+    pub timeout: Option<Duration>,
+    /// Size of the rayon pool; rayon picks one per core when unset.
+    pub threads: Option<usize>,
+    /// Count allocations while the part runs. Needs the binary to install
+    /// [`memory::CountingAllocator`].
+    pub memory: bool,
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Solved {
+        answer: String,
+        elapsed: Duration,
+        memory: Option<MemoryStats>,
+    },
+    TimedOut(Duration),
+    /// Timed out and did not stop within the grace period. The part's thread
+    /// and rayon pool are still running in the background, using CPU and
+    /// memory until the process exits.
+    Abandoned(Duration),
+    Panicked(String),
+}
+
+impl Outcome {
+    pub fn answer(&self) -> Option<&str> {
+        match self {
+            Outcome::Solved { answer, .. } => Some(answer),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Solved { .. } => "solved",
+            Outcome::TimedOut(_) => "timed_out",
+            Outcome::Abandoned(_) => "abandoned",
+            Outcome::Panicked(_) => "panicked",
+        }
+    }
+
+    pub fn memory(&self) -> Option<MemoryStats> {
+        match self {
+            Outcome::Solved { memory, .. } => *memory,
+            _ => None,
+        }
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+        match self {
+            Outcome::Solved { elapsed, .. } => Some(*elapsed),
+            Outcome::TimedOut(elapsed) | Outcome::Abandoned(elapsed) => Some(*elapsed),
+            Outcome::Panicked(_) => None,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Solved { answer, .. } => write!(f, "{}", answer),
+            Outcome::TimedOut(after) => write!(f, "timed out after {:.1?}", after),
+            Outcome::Abandoned(after) => write!(
+                f,
+                "timed out after {:.1?}, still running in the background",
+                after
+            ),
+            Outcome::Panicked(message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+
+    "unknown panic".to_owned()
+}
+
+/// Runs one part on its own thread and rayon pool, and cancels it after the
+/// timeout.
+///
+/// A part that never reaches a [`cancel::checkpoint`] cannot be stopped: its
+/// thread and pool are detached and keep running until the process exits, and
+/// it is reported as [`Outcome::Abandoned`]. Later runs share the CPU with it.
+pub fn run_part(solver: Solver, input: &str, options: &RunOptions) -> Outcome {
+    let timeout = options.timeout;
+    let threads = options.threads.unwrap_or(0);
+    let count_memory = options.memory;
+    let token = CancelToken::new();
+    let run_token = token.clone();
+    let input = input.to_owned();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let worker_token = run_token.clone();
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .start_handler(move |_| cancel::set_current(Some(worker_token.clone())))
+            .build()
+            .expect("failed to build thread pool");
+
+        cancel::set_current(Some(run_token));
+
+        if count_memory {
+            memory::reset();
+            memory::set_enabled(true);
+        }
+
+        let start = Instant::now();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| pool.install(|| solver(&input))));
+        let elapsed = start.elapsed();
+        let mut stats = None;
+
+        if count_memory {
+            stats = Some(memory::stats());
+            memory::set_enabled(false);
+        }
+
+        let _ = sender.send((result, elapsed, stats));
+    });
+
+    let received = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).ok(),
+        None => receiver.recv().ok(),
+    };
+
+    match received {
+        Some((Ok(answer), elapsed, memory)) => Outcome::Solved {
+            answer,
+            elapsed,
+            memory,
+        },
+        Some((Err(payload), elapsed, _)) if payload.is::<Cancelled>() => Outcome::TimedOut(elapsed),
+        Some((Err(payload), _, _)) => Outcome::Panicked(panic_message(payload.as_ref())),
+        None => {
+            let timeout = timeout.unwrap_or_default();
+            token.cancel();
+
+            match receiver.recv_timeout(CANCEL_GRACE) {
+                Ok(_) => Outcome::TimedOut(timeout),
+                Err(_) => Outcome::Abandoned(timeout),
+            }
+        }
+    }
+}
+
+/// Result of one run, in the shape written by `--format json`.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub input: &'static str,
+    pub status: &'static str,
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub elapsed_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryStats>,
+}
+
+impl Report {
+    pub fn new(
+        day: u8,
+        one: bool,
+        variant: &'static str,
+        input: &'static str,
+        outcome: &Outcome,
+    ) -> Self {
+        Report {
+            day,
+            part: if one { 1 } else { 2 },
+            variant,
+            input,
+            status: outcome.status(),
+            answer: outcome.answer().map(str::to_owned),
+            error: match outcome {
+                Outcome::Solved { .. } => None,
+                _ => Some(outcome.to_string()),
+            },
+            elapsed_ms: outcome
+                .elapsed()
+                .map(|elapsed| elapsed.as_secs_f64() * 1000.0),
+            memory: outcome.memory(),
+        }
+    }
+}
+
+/// Run of one variant checked against the answer recorded for its input.
+#[derive(Serialize)]
+pub struct Verification {
+    #[serde(flatten)]
+    pub report: Report,
+    /// `None` for inputs without a recorded answer.
+    pub expected: Option<&'static str>,
+    /// `None` when the part solved an input without a recorded answer, as
+    /// there is nothing to check the answer against.
+    pub correct: Option<bool>,
+}
+
+/// Runs `variant` of a part on each named input and checks the answers
+/// recorded for them, if any.
+pub fn verify(
+    solution: &Solution,
+    one: bool,
+    variant: &Variant,
+    inputs: &[(&'static str, &str, Option<&'static str>)],
+    options: &RunOptions,
+) -> Vec<Verification> {
+    inputs
+        .iter()
+        .map(|&(input_name, input, expected)| {
+            let outcome = run_part(variant.solver, input, options);
+            let correct = match expected {
+                Some(expected) => Some(outcome.answer() == Some(expected)),
+                None => outcome.answer().is_none().then_some(false),
+            };
+
+            Verification {
+                report: Report::new(solution.day, one, variant.name, input_name, &outcome),
+                expected,
+                correct,
+            }
+        })
+        .collect()
+}
+
+/// Every variant of one part run on the same input.
+#[derive(Serialize)]
+pub struct Comparison {
+    pub day: u8,
+    pub part: u8,
+    pub input: &'static str,
+    pub agree: bool,
+    pub results: Vec<Report>,
+}
+
+/// Runs every variant of a part on each named input and checks that all of
+/// them solve it with the same answer.
+pub fn compare(
+    solution: &Solution,
+    one: bool,
+    inputs: &[(&'static str, &str)],
+    options: &RunOptions,
+) -> Vec<Comparison> {
+    inputs
+        .iter()
+        .map(|(input_name, input)| {
+            let outcomes: Vec<_> = solution
+                .variants(one)
+                .iter()
+                .map(|variant| (variant.name, run_part(variant.solver, input, options)))
+                .collect();
+
+            let first = outcomes.first().and_then(|(_, outcome)| outcome.answer());
+            let agree = first.is_some()
+                && outcomes
+                    .iter()
+                    .all(|(_, outcome)| outcome.answer() == first);
+
+            Comparison {
+                day: solution.day,
+                part: if one { 1 } else { 2 },
+                input: input_name,
+                agree,
+                results: outcomes
+                    .iter()
+                    .map(|(name, outcome)| {
+                        Report::new(solution.day, one, name, input_name, outcome)
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Thread counts for `runs` runs: 1 first, then doubling up to the number of
+/// cores, starting over when they run out. Goes to at least 4 so that
+/// machines with few cores still see different pool sizes.
+pub fn thread_counts(runs: usize) -> Vec<usize> {
+    let cores = thread::available_parallelism()
+        .map_or(1, |cores| cores.get())
+        .max(4);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |count| Some(count * 2))
+        .take_while(|&count| count <= cores)
+        .collect();
+
+    if counts.last() != Some(&cores) {
+        counts.push(cores);
+    }
+
+    counts.into_iter().cycle().take(runs).collect()
+}
+
+/// One run of a determinism check.
+#[derive(Serialize)]
+pub struct ThreadedRun {
+    pub threads: usize,
+    #[serde(flatten)]
+    pub report: Report,
+}
+
+/// Repeated runs of one part on different thread counts.
+#[derive(Serialize)]
+pub struct Determinism {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    /// Every run solved the part with the same answer.
+    pub deterministic: bool,
+    pub runs: Vec<ThreadedRun>,
+}
+
+/// Runs a part `runs` times with the pool sizes from [`thread_counts`] to
+/// catch answers that depend on scheduling.
+pub fn determinism(
+    solution: &Solution,
+    one: bool,
+    variant: &Variant,
+    input: &str,
+    runs: usize,
+    options: &RunOptions,
+) -> Determinism {
+    let runs: Vec<ThreadedRun> = thread_counts(runs)
+        .into_iter()
+        .map(|threads| {
+            let options = RunOptions {
+                threads: Some(threads),
+                ..*options
+            };
+            let outcome = run_part(variant.solver, input, &options);
+
+            ThreadedRun {
+                threads,
+                report: Report::new(solution.day, one, variant.name, "real", &outcome),
+            }
+        })
+        .collect();
+
+    let first = runs.first().and_then(|run| run.report.answer.as_ref());
+    let deterministic =
+        first.is_some() && runs.iter().all(|run| run.report.answer.as_ref() == first);
+
+    Determinism {
+        day: solution.day,
+        part: if one { 1 } else { 2 },
+        variant: variant.name,
+        deterministic,
+        runs,
+    }
+}
+
+/// Timings of repeated runs of one part.
+#[derive(Serialize)]
+pub struct Bench {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub runs: usize,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Runs a part `runs` times, stopping at the first run that does not solve it.
+pub fn bench(
+    solver: Solver,
+    input: &str,
+    runs: usize,
+    options: &RunOptions,
+) -> Result<Vec<Duration>, Outcome> {
+    let mut samples = Vec::with_capacity(runs);
+
+    for _ in 0..runs {
+        match run_part(solver, input, options) {
+            Outcome::Solved { elapsed, .. } => samples.push(elapsed),
+            outcome => return Err(outcome),
+        }
+    }
+
+    Ok(samples)
+}
+
+impl Bench {
+    pub fn new(day: u8, one: bool, variant: &'static str, samples: &[Duration]) -> Self {
+        let millis: Vec<f64> = samples
+            .iter()
+            .map(|sample| sample.as_secs_f64() * 1000.0)
+            .collect();
+
+        Bench {
+            day,
+            part: if one { 1 } else { 2 },
+            variant,
+            runs: samples.len(),
+            min_ms: millis.iter().copied().fold(f64::INFINITY, f64::min),
+            mean_ms: millis.iter().sum::<f64>() / millis.len().max(1) as f64,
+            max_ms: millis.iter().copied().fold(0.0, f64::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use crate::{
+        cancel, memory,
+        runner::{
+            bench, compare, determinism, run_part, thread_counts, verify, Outcome, RunOptions,
+        },
+        solution::{self, Variant},
+    };
+
+    fn spin(_input: &str) -> String {
+        loop {
+            cancel::checkpoint();
+        }
+    }
+
+    fn sleep(_input: &str) -> String {
+        thread::sleep(Duration::from_secs(2));
+        String::new()
+    }
+
+    fn echo(input: &str) -> String {
+        input.to_owned()
+    }
+
+    #[test]
+    fn timeout_test() {
+        let options = RunOptions {
+            timeout: Some(Duration::from_millis(50)),
+            threads: Some(2),
+            ..RunOptions::default()
+        };
+        let outcome = run_part(spin, "", &options);
+        assert!(matches!(outcome, Outcome::TimedOut(_)));
+
+        let outcome = run_part(sleep, "", &options);
+        assert!(matches!(outcome, Outcome::Abandoned(_)));
+
+        let options = RunOptions {
+            timeout: Some(Duration::from_secs(5)),
+            threads: None,
+            ..RunOptions::default()
+        };
+        let outcome = run_part(echo, "answer", &options);
+        assert!(matches!(outcome, Outcome::Solved { answer, .. } if answer == "answer"));
+    }
+
+    #[test]
+    fn memory_test() {
+        let _lock = memory::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        let options = RunOptions {
+            memory: true,
+            ..RunOptions::default()
+        };
+        let outcome = run_part(echo, "answer", &options);
+
+        assert!(outcome.memory().is_some());
+        assert!(!memory::enabled());
+
+        memory::set_enabled(true);
+        run_part(echo, "answer", &RunOptions::default());
+        assert!(memory::enabled());
+        memory::set_enabled(false);
+    }
+
+    #[test]
+    fn verify_test() {
+        let solution = solution::find(1).unwrap();
+        let variant = &solution.variants(true)[0];
+        let inputs = [
+            (
+                "example",
+                solution.example,
+                Some(solution.example_answer(true)),
+            ),
+            ("real", solution.input, Some(solution.answer(true))),
+            ("custom", "1 2\n", None),
+            ("wrong", "1 2\n", Some("0")),
+        ];
+
+        let correct: Vec<Option<bool>> =
+            verify(solution, true, variant, &inputs, &RunOptions::default())
+                .iter()
+                .map(|verification| verification.correct)
+                .collect();
+
+        assert_eq!(correct, vec![Some(true), Some(true), None, Some(false)]);
+    }
+
+    #[test]
+    fn compare_test() {
+        let solution = solution::find(7).unwrap();
+
+        let inputs = [("example", solution.example), ("real", solution.input)];
+
+        for comparison in compare(solution, true, &inputs, &RunOptions::default()) {
+            assert_eq!(comparison.results.len(), 2);
+            assert!(comparison.agree);
+        }
+    }
+
+    fn pool_size(_input: &str) -> String {
+        rayon::current_num_threads().to_string()
+    }
+
+    #[test]
+    fn determinism_test() {
+        let counts = thread_counts(6);
+        assert_eq!(counts.len(), 6);
+        assert_eq!(&counts[..3], &[1, 2, 4]);
+
+        let solution = solution::find(7).unwrap();
+        let variant = &solution.part_2[0];
+        let check = determinism(
+            solution,
+            false,
+            variant,
+            solution.example,
+            3,
+            &RunOptions::default(),
+        );
+        assert!(check.deterministic);
+        assert_eq!(check.runs.len(), 3);
+
+        let variant = Variant::new("pool_size", pool_size);
+        let check = determinism(solution, false, &variant, "", 2, &RunOptions::default());
+        assert!(!check.deterministic);
+    }
+
+    #[test]
+    fn bench_test() {
+        let samples = bench(echo, "answer", 3, &RunOptions::default()).unwrap();
+        assert_eq!(samples.len(), 3);
+
+        let options = RunOptions {
+            timeout: Some(Duration::from_millis(50)),
+            threads: None,
+            ..RunOptions::default()
+        };
+        assert!(matches!(
+            bench(spin, "", 3, &options),
+            Err(Outcome::TimedOut(_))
+        ));
+    }
+}
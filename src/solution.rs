@@ -0,0 +1,166 @@
+use crate::{day01, day02, day03, day04, day05, day06, day07, day08, day09, input, output};
+
+pub type Solver = fn(&str) -> String;
+
+/// One named implementation of a part.
+pub struct Variant {
+    pub name: &'static str,
+    pub solver: Solver,
+}
+
+impl Variant {
+    pub const fn new(name: &'static str, solver: Solver) -> Self {
+        Variant { name, solver }
+    }
+}
+
+pub struct Solution {
+    pub day: u8,
+    pub input: &'static str,
+    pub example: &'static str,
+    /// Expected answers to parts 1 and 2 for `input`.
+    pub answers: [&'static str; 2],
+    /// Expected answers to parts 1 and 2 for `example`.
+    pub example_answers: [&'static str; 2],
+    /// Implementations of part 1; the first one is the default.
+    pub part_1: &'static [Variant],
+    /// Implementations of part 2; the first one is the default.
+    pub part_2: &'static [Variant],
+}
+
+impl Solution {
+    pub fn variants(&self, one: bool) -> &'static [Variant] {
+        if one {
+            self.part_1
+        } else {
+            self.part_2
+        }
+    }
+
+    pub fn part(&self, one: bool) -> Solver {
+        self.variants(one)[0].solver
+    }
+
+    pub fn answer(&self, one: bool) -> &'static str {
+        self.answers[if one { 0 } else { 1 }]
+    }
+
+    pub fn example_answer(&self, one: bool) -> &'static str {
+        self.example_answers[if one { 0 } else { 1 }]
+    }
+
+    pub fn variant(&self, one: bool, name: &str) -> Option<&'static Variant> {
+        self.variants(one)
+            .iter()
+            .find(|variant| variant.name == name)
+    }
+}
+
+pub const SOLUTIONS: &[Solution] = &[
+    Solution {
+        day: 1,
+        input: input::DAY_01,
+        example: input::example::DAY_01,
+        answers: [output::DAY_01_1, output::DAY_01_2],
+        example_answers: [output::example::DAY_01_1, output::example::DAY_01_2],
+        part_1: &[
+            Variant::new("default", day01::part_1),
+            Variant::new("streaming", day01::part_1_streaming),
+        ],
+        part_2: &[
+            Variant::new("default", day01::part_2),
+            Variant::new("streaming", day01::part_2_streaming),
+        ],
+    },
+    Solution {
+        day: 2,
+        input: input::DAY_02,
+        example: input::example::DAY_02,
+        answers: [output::DAY_02_1, output::DAY_02_2],
+        example_answers: [output::example::DAY_02_1, output::example::DAY_02_2],
+        part_1: &[Variant::new("default", day02::part_1)],
+        part_2: &[Variant::new("default", day02::part_2)],
+    },
+    Solution {
+        day: 3,
+        input: input::DAY_03,
+        example: input::example::DAY_03,
+        answers: [output::DAY_03_1, output::DAY_03_2],
+        example_answers: [output::example::DAY_03_1, output::example::DAY_03_2],
+        part_1: &[
+            Variant::new("default", day03::part_1),
+            Variant::new("streaming", day03::part_1_streaming),
+        ],
+        part_2: &[
+            Variant::new("default", day03::part_2),
+            Variant::new("streaming", day03::part_2_streaming),
+        ],
+    },
+    Solution {
+        day: 4,
+        input: input::DAY_04,
+        example: input::example::DAY_04,
+        answers: [output::DAY_04_1, output::DAY_04_2],
+        example_answers: [output::example::DAY_04_1, output::example::DAY_04_2],
+        part_1: &[Variant::new("default", day04::part_1)],
+        part_2: &[Variant::new("default", day04::part_2)],
+    },
+    Solution {
+        day: 5,
+        input: input::DAY_05,
+        example: input::example::DAY_05,
+        answers: [output::DAY_05_1, output::DAY_05_2],
+        example_answers: [output::example::DAY_05_1, output::example::DAY_05_2],
+        part_1: &[Variant::new("default", day05::part_1)],
+        part_2: &[Variant::new("default", day05::part_2)],
+    },
+    Solution {
+        day: 6,
+        input: input::DAY_06,
+        example: input::example::DAY_06,
+        answers: [output::DAY_06_1, output::DAY_06_2],
+        example_answers: [output::example::DAY_06_1, output::example::DAY_06_2],
+        part_1: &[Variant::new("default", day06::part_1)],
+        part_2: &[Variant::new("default", day06::part_2)],
+    },
+    Solution {
+        day: 7,
+        input: input::DAY_07,
+        example: input::example::DAY_07,
+        answers: [output::DAY_07_1, output::DAY_07_2],
+        example_answers: [output::example::DAY_07_1, output::example::DAY_07_2],
+        part_1: &[
+            Variant::new("forward", day07::part_1),
+            Variant::new("reverse", day07::part_1_reverse),
+        ],
+        part_2: &[
+            Variant::new("forward", day07::part_2),
+            Variant::new("reverse", day07::part_2_reverse),
+        ],
+    },
+    Solution {
+        day: 8,
+        input: input::DAY_08,
+        example: input::example::DAY_08,
+        answers: [output::DAY_08_1, output::DAY_08_2],
+        example_answers: [output::example::DAY_08_1, output::example::DAY_08_2],
+        part_1: &[Variant::new("default", day08::part_1)],
+        part_2: &[Variant::new("default", day08::part_2)],
+    },
+    Solution {
+        day: 9,
+        input: input::DAY_09,
+        example: input::example::DAY_09,
+        answers: [output::DAY_09_1, output::DAY_09_2],
+        example_answers: [output::example::DAY_09_1, output::example::DAY_09_2],
+        part_1: &[Variant::new("default", day09::part_1)],
+        part_2: &[
+            Variant::new("spans", day09::part_2),
+            Variant::new("blocks", day09::part_2_blocks),
+        ],
+    },
+];
+
+pub fn find(day: u8) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|solution| solution.day == day)
+}
@@ -0,0 +1,228 @@
+use crate::grid::{GridMap, Position};
+
+/// Disjoint sets over `0..len` with path compression and union by rank.
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    components: usize,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        UnionFind {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            size: vec![1; len],
+            components: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Representative of the set containing `element`.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut current = element;
+
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Merges the sets of `a` and `b`. Returns false if they were already one.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+
+        self.components -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Size of the set containing `element`.
+    pub fn size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Number of disjoint sets.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// Elements in the same set as `element`, in ascending order.
+    pub fn members(&mut self, element: usize) -> Vec<usize> {
+        let root = self.find(element);
+
+        (0..self.len())
+            .filter(|other| self.find(*other) == root)
+            .collect()
+    }
+
+    /// Every set with its elements in ascending order, ordered by smallest
+    /// element.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut index_of_root = vec![usize::MAX; self.len()];
+        let mut components: Vec<Vec<usize>> = vec![];
+
+        for element in 0..self.len() {
+            let root = self.find(element);
+
+            if index_of_root[root] == usize::MAX {
+                index_of_root[root] = components.len();
+                components.push(vec![]);
+            }
+
+            components[index_of_root[root]].push(element);
+        }
+
+        components
+    }
+}
+
+/// Connected regions of equal, orthogonally adjacent cells in a grid.
+pub struct Regions {
+    labels: GridMap<usize>,
+    sizes: Vec<usize>,
+}
+
+impl Regions {
+    pub fn label<T: PartialEq>(grid: &GridMap<T>) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut sets = UnionFind::new(width * height);
+
+        for (position, cell) in grid.iter() {
+            let index = position.y * width + position.x;
+
+            if position.x + 1 < width && grid[position.right()] == *cell {
+                sets.union(index, index + 1);
+            }
+
+            if position.y + 1 < height && grid[position.down()] == *cell {
+                sets.union(index, index + width);
+            }
+        }
+
+        let mut labels = GridMap::new(width, height, 0);
+        let mut sizes = vec![];
+
+        for (label, members) in sets.components().into_iter().enumerate() {
+            for index in members.iter() {
+                labels[Position::new(index % width, index / width)] = label;
+            }
+
+            sizes.push(members.len());
+        }
+
+        Regions { labels, sizes }
+    }
+
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Region label of `position`. Labels are numbered in row-major order of
+    /// each region's first cell.
+    pub fn region(&self, position: Position) -> usize {
+        self.labels[position]
+    }
+
+    pub fn size(&self, region: usize) -> usize {
+        self.sizes[region]
+    }
+
+    pub fn labels(&self) -> &GridMap<usize> {
+        &self.labels
+    }
+
+    /// Cells of `region` in row-major order.
+    pub fn members(&self, region: usize) -> Vec<Position> {
+        self.labels
+            .iter()
+            .filter(|(_, label)| **label == region)
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        grid::{GridMap, Position},
+        union_find::{Regions, UnionFind},
+    };
+
+    #[test]
+    fn union_find_test() {
+        let mut sets = UnionFind::new(6);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(4, 1));
+        assert!(sets.union(2, 3));
+        assert!(!sets.union(0, 4));
+
+        assert!(sets.connected(0, 4));
+        assert!(!sets.connected(0, 2));
+        assert_eq!(sets.size(4), 3);
+        assert_eq!(sets.component_count(), 3);
+        assert_eq!(sets.members(1), vec![0, 1, 4]);
+        assert_eq!(sets.components(), vec![vec![0, 1, 4], vec![2, 3], vec![5]]);
+    }
+
+    #[test]
+    fn regions_test() {
+        let grid = GridMap::parse("AAAA\nBBCD\nBBCC\nEEEC\n", |c| c);
+        let regions = Regions::label(&grid);
+
+        assert_eq!(regions.count(), 5);
+        assert_eq!(regions.region(Position::new(3, 0)), 0);
+        assert_eq!(regions.size(regions.region(Position::new(2, 1))), 4);
+        assert_eq!(regions.size(regions.region(Position::new(3, 1))), 1);
+        assert_eq!(
+            regions.members(regions.region(Position::new(1, 2))),
+            vec![
+                Position::new(0, 1),
+                Position::new(1, 1),
+                Position::new(0, 2),
+                Position::new(1, 2)
+            ]
+        );
+    }
+}